@@ -6,6 +6,7 @@
  */
 
 use std::collections::BTreeMap;
+use std::fs;
 use std::sync::Arc;
 
 use anyhow::anyhow;
@@ -40,6 +41,7 @@ use cmdlib::args::MononokeMatches;
 use cmdlib::helpers;
 use cmdlib_x_repo::create_commit_syncers_from_matches;
 use commit_graph::CommitGraph;
+use commit_graph::CommitGraphRef;
 use commit_graph::CommitGraphWriter;
 use context::CoreContext;
 use cross_repo_sync::create_commit_syncer_lease;
@@ -62,6 +64,7 @@ use filestore::FilestoreConfig;
 use filestore::FilestoreConfigRef;
 use futures::stream;
 use futures::try_join;
+use futures::StreamExt;
 use futures::TryFutureExt;
 use itertools::Itertools;
 use live_commit_sync_config::CfgrLiveCommitSyncConfig;
@@ -122,15 +125,25 @@ const VERIFY_WC_SUBCOMMAND: &str = "verify-wc";
 const VERIFY_BOOKMARKS_SUBCOMMAND: &str = "verify-bookmarks";
 const HASH_ARG: &str = "HASH";
 const LARGE_REPO_HASH_ARG: &str = "large-repo-hash";
+const TO_LARGE_REPO_HASH_ARG: &str = "to-hash";
 const UPDATE_LARGE_REPO_BOOKMARKS: &str = "update-large-repo-bookmarks";
 const LIMIT_ARG: &str = "limit";
 const NO_BOOKMARK_UPDATES: &str = "no-bookmark-updates";
+const RESET_CHECKPOINT_ARG: &str = "reset-checkpoint";
+const CONCURRENCY_ARG: &str = "concurrency";
+const START_AFTER_ARG: &str = "start-after";
+/// Default width of the buffered stream used to derive data for and commit
+/// `--update-large-repo-bookmarks` updates, when `--concurrency` isn't given.
+const DEFAULT_UPDATE_LARGE_REPO_BOOKMARKS_CONCURRENCY: usize = 10;
 const LARGE_REPO_BOOKMARK_ARG: &str = "large-repo-bookmark";
 const CHANGE_MAPPING_VERSION_SUBCOMMAND: &str = "change-mapping-version";
+const SKIP_PREFLIGHT_ARG: &str = "skip-preflight";
 const INSERT_SUBCOMMAND: &str = "insert";
 const REWRITTEN_SUBCOMMAND: &str = "rewritten";
 const EQUIVALENT_WORKING_COPY_SUBCOMMAND: &str = "equivalent-working-copy";
 const NOT_SYNC_CANDIDATE_SUBCOMMAND: &str = "not-sync-candidate";
+const FROM_FILE_ARG: &str = "from-file";
+const FROM_FILE_CHUNK_SIZE: usize = 100;
 const SOURCE_HASH_ARG: &str = "source-hash";
 const TARGET_HASH_ARG: &str = "target-hash";
 const VIA_EXTRAS_ARG: &str = "via-extra";
@@ -140,6 +153,7 @@ const SUBCOMMAND_BY_VERSION: &str = "by-version";
 const SUBCOMMAND_LIST: &str = "list";
 const ARG_VERSION_NAME: &str = "version-name";
 const ARG_WITH_CONTENTS: &str = "with-contents";
+const OUTPUT_ARG: &str = "output";
 
 #[facet::container]
 #[derive(Clone)]
@@ -208,12 +222,40 @@ enum UpdateLargeRepoBookmarksMode {
     DryRun,
 }
 
+/// Output mode for the `crossrepo` subcommands that support it (`map`,
+/// `verify-bookmarks`, `config list` and `config by-version`): `Text`
+/// preserves the existing human-oriented `println!`/log output, `Json` emits
+/// a single machine-readable JSON value on stdout so results can be piped
+/// into other tooling without scraping logs. The other subcommands ignore it.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum OutputFormat {
+    Text,
+    Json,
+}
+
+impl OutputFormat {
+    fn is_json(self) -> bool {
+        self == Self::Json
+    }
+
+    fn parse(matches: &ArgMatches<'_>) -> Result<Self, Error> {
+        match matches.value_of(OUTPUT_ARG) {
+            Some("json") => Ok(Self::Json),
+            Some("text") | None => Ok(Self::Text),
+            Some(other) => Err(format_err!("invalid value for --{}: {}", OUTPUT_ARG, other)),
+        }
+    }
+}
+
 #[derive(Debug, Clone, PartialEq)]
 enum VerifyRunMode {
     JustVerify,
     UpdateLargeRepoBookmarks {
         limit: Option<usize>,
         mode: UpdateLargeRepoBookmarksMode,
+        reset_checkpoint: bool,
+        concurrency: usize,
+        start_after: Option<String>,
     },
 }
 
@@ -224,6 +266,7 @@ pub async fn subcommand_crossrepo<'a>(
     sub_m: &'a ArgMatches<'_>,
 ) -> Result<(), SubcommandError> {
     let config_store = matches.config_store();
+    let output = OutputFormat::parse(sub_m)?;
 
     let ctx = CoreContext::new_with_logger_and_client_info(
         fb,
@@ -258,7 +301,7 @@ pub async fn subcommand_crossrepo<'a>(
                 x_repo_syncer_lease,
             );
             let hash = sub_sub_m.value_of(HASH_ARG).unwrap().to_owned();
-            subcommand_map(ctx, commit_syncer, hash).await
+            subcommand_map(ctx, commit_syncer, hash, output).await
         }
         (VERIFY_WC_SUBCOMMAND, Some(sub_sub_m)) => {
             let source_repo_id =
@@ -280,14 +323,31 @@ pub async fn subcommand_crossrepo<'a>(
                 helpers::csid_resolve(&ctx, large_repo, large_hash).await?
             };
 
-            verify_working_copy(
-                &ctx,
-                &commit_syncer,
-                large_hash,
-                commit_syncer.live_commit_sync_config.clone(),
-            )
-            .await
-            .map_err(|e| e.into())
+            match sub_sub_m.value_of(TO_LARGE_REPO_HASH_ARG) {
+                Some(to_hash) => {
+                    let to_hash = {
+                        let large_repo = commit_syncer.get_large_repo();
+                        helpers::csid_resolve(&ctx, large_repo, to_hash.to_owned()).await?
+                    };
+                    let concurrency = sub_sub_m
+                        .value_of(LIMIT_ARG)
+                        .map(str::parse::<usize>)
+                        .transpose()
+                        .map_err(anyhow::Error::msg)?
+                        .unwrap_or(DEFAULT_VERIFY_WC_RANGE_CONCURRENCY);
+
+                    subcommand_verify_wc_range(ctx, commit_syncer, large_hash, to_hash, concurrency)
+                        .await
+                }
+                None => verify_working_copy(
+                    &ctx,
+                    &commit_syncer,
+                    large_hash,
+                    commit_syncer.live_commit_sync_config.clone(),
+                )
+                .await
+                .map_err(|e| e.into()),
+            }
         }
         (VERIFY_BOOKMARKS_SUBCOMMAND, Some(sub_sub_m)) => {
             let (source_repo, target_repo, mapping) =
@@ -305,6 +365,14 @@ pub async fn subcommand_crossrepo<'a>(
                         .map(str::parse::<usize>)
                         .transpose()
                         .map_err(anyhow::Error::msg)?,
+                    reset_checkpoint: sub_sub_m.is_present(RESET_CHECKPOINT_ARG),
+                    concurrency: sub_sub_m
+                        .value_of(CONCURRENCY_ARG)
+                        .map(str::parse::<usize>)
+                        .transpose()
+                        .map_err(anyhow::Error::msg)?
+                        .unwrap_or(DEFAULT_UPDATE_LARGE_REPO_BOOKMARKS_CONCURRENCY),
+                    start_after: sub_sub_m.value_of(START_AFTER_ARG).map(|s| s.to_string()),
                 }
             } else {
                 VerifyRunMode::JustVerify
@@ -321,6 +389,7 @@ pub async fn subcommand_crossrepo<'a>(
                 mode,
                 Arc::new(live_commit_sync_config),
                 matches,
+                output,
             )
             .await
         }
@@ -329,7 +398,14 @@ pub async fn subcommand_crossrepo<'a>(
             let repo_id = args::not_shardmanager_compatible::get_repo_id(config_store, matches)?;
             let live_commit_sync_config =
                 get_live_commit_sync_config(&ctx, fb, matches, repo_id).await?;
-            run_config_sub_subcommand(matches, sub_sub_m, repo_id, live_commit_sync_config).await
+            run_config_sub_subcommand(
+                matches,
+                sub_sub_m,
+                repo_id,
+                live_commit_sync_config,
+                output,
+            )
+            .await
         }
         (PUSHREDIRECTION_SUBCOMMAND, Some(sub_sub_m)) => {
             let source_repo_id =
@@ -362,17 +438,18 @@ async fn run_config_sub_subcommand<'a>(
     config_subcommand_matches: &'a ArgMatches<'a>,
     repo_id: RepositoryId,
     live_commit_sync_config: CfgrLiveCommitSyncConfig,
+    output: OutputFormat,
 ) -> Result<(), SubcommandError> {
     match config_subcommand_matches.subcommand() {
         (SUBCOMMAND_BY_VERSION, Some(sub_m)) => {
             let version_name: String = sub_m.value_of(ARG_VERSION_NAME).unwrap().to_string();
-            subcommand_by_version(repo_id, live_commit_sync_config, version_name)
+            subcommand_by_version(repo_id, live_commit_sync_config, version_name, output)
                 .await
                 .map_err(|e| e.into())
         }
         (SUBCOMMAND_LIST, Some(sub_m)) => {
             let with_contents = sub_m.is_present(ARG_WITH_CONTENTS);
-            subcommand_list(repo_id, live_commit_sync_config, with_contents)
+            subcommand_list(repo_id, live_commit_sync_config, with_contents, output)
                 .await
                 .map_err(|e| e.into())
         }
@@ -523,6 +600,15 @@ async fn run_pushredirection_subcommand<'a>(
                 .map(NonRootMPath::new)
                 .transpose()?;
 
+            preflight_check_mapping_change(
+                &ctx,
+                sub_m,
+                &commit_syncer,
+                large_bookmark_value.0,
+                &live_commit_sync_config,
+            )
+            .await?;
+
             let large_cs_id = create_commit_for_mapping_change(
                 &ctx,
                 sub_m,
@@ -579,6 +665,46 @@ async fn run_pushredirection_subcommand<'a>(
     }
 }
 
+/// Confirm that `large_bookmark_value` is still equivalent to its small-repo
+/// counterpart under the mapping version that's about to be replaced, so that
+/// `change-mapping-version` doesn't bake an already-broken boundary into
+/// history. Skipped if `--skip-preflight` is passed.
+async fn preflight_check_mapping_change<'a>(
+    ctx: &CoreContext,
+    sub_m: &'a ArgMatches<'a>,
+    commit_syncer: &CommitSyncer<SqlSyncedCommitMapping, Repo>,
+    large_bookmark_value: ChangesetId,
+    live_commit_sync_config: &Arc<dyn LiveCommitSyncConfig>,
+) -> Result<(), Error> {
+    if sub_m.is_present(SKIP_PREFLIGHT_ARG) {
+        warn!(
+            ctx.logger(),
+            "{} passed, skipping working copy equivalence preflight check", SKIP_PREFLIGHT_ARG
+        );
+        return Ok(());
+    }
+
+    info!(
+        ctx.logger(),
+        "checking that {} is equivalent to its small repo counterpart under the outgoing mapping version",
+        large_bookmark_value,
+    );
+    verify_working_copy(
+        ctx,
+        commit_syncer,
+        large_bookmark_value,
+        live_commit_sync_config.clone(),
+    )
+    .await
+    .with_context(|| {
+        format!(
+            "working copies are not equivalent under the outgoing mapping version, \
+            refusing to change mapping version (pass --{} to override)",
+            SKIP_PREFLIGHT_ARG
+        )
+    })
+}
+
 async fn change_mapping_via_extras<'a>(
     ctx: &CoreContext,
     matches: &'a MononokeMatches<'a>,
@@ -629,6 +755,16 @@ async fn change_mapping_via_extras<'a>(
         .value_of(DUMP_MAPPING_LARGE_REPO_PATH_ARG)
         .map(NonRootMPath::new)
         .transpose()?;
+
+    preflight_check_mapping_change(
+        ctx,
+        sub_m,
+        commit_syncer,
+        large_bookmark_value.0,
+        live_commit_sync_config,
+    )
+    .await?;
+
     let large_cs_id = create_commit_for_mapping_change(
         ctx,
         sub_m,
@@ -697,79 +833,139 @@ async fn run_insert_subcommand<'a>(
 
     match insert_subcommand_matches.subcommand() {
         (REWRITTEN_SUBCOMMAND, Some(sub_m)) => {
-            let (source_cs_id, target_cs_id, mapping_version) =
-                get_source_target_cs_ids_and_version(&ctx, sub_m, &commit_syncer).await?;
-            let small_repo_id = commit_syncer.get_small_repo().repo_identity().id();
-            let large_repo_id = commit_syncer.get_large_repo().repo_identity().id();
-
-            let mapping_entry = if small_repo_id == source_repo.repo_identity().id() {
-                SyncedCommitMappingEntry {
-                    large_repo_id,
-                    small_repo_id,
-                    small_bcs_id: source_cs_id,
-                    large_bcs_id: target_cs_id,
-                    version_name: Some(mapping_version),
-                    source_repo: Some(commit_syncer.get_source_repo_type()),
-                }
+            if let Some(path) = sub_m.value_of(FROM_FILE_ARG) {
+                let (rows, parse_failures) =
+                    resolve_pair_rows(&ctx, &commit_syncer, read_from_file_lines(path)?).await?;
+                let (successes, skipped, insert_failures) = bulk_insert_pair_rows(
+                    &ctx,
+                    &mapping,
+                    &commit_syncer,
+                    &source_repo,
+                    rows,
+                    PairRowKind::Rewritten,
+                )
+                .await?;
+                report_bulk_insert(&ctx, successes, skipped, parse_failures, insert_failures)
             } else {
-                SyncedCommitMappingEntry {
-                    large_repo_id,
-                    small_repo_id,
-                    small_bcs_id: target_cs_id,
-                    large_bcs_id: source_cs_id,
-                    version_name: Some(mapping_version),
-                    source_repo: Some(commit_syncer.get_source_repo_type()),
-                }
-            };
+                let (source_cs_id, target_cs_id, mapping_version) =
+                    get_source_target_cs_ids_and_version(&ctx, sub_m, &commit_syncer).await?;
+                let small_repo_id = commit_syncer.get_small_repo().repo_identity().id();
+                let large_repo_id = commit_syncer.get_large_repo().repo_identity().id();
 
-            let res = mapping.add(&ctx, mapping_entry).await?;
-            if res {
-                info!(
-                    ctx.logger(),
-                    "successfully inserted rewritten mapping entry"
-                );
-                Ok(())
-            } else {
-                Err(anyhow!("failed to insert entry").into())
+                let mapping_entry = if small_repo_id == source_repo.repo_identity().id() {
+                    SyncedCommitMappingEntry {
+                        large_repo_id,
+                        small_repo_id,
+                        small_bcs_id: source_cs_id,
+                        large_bcs_id: target_cs_id,
+                        version_name: Some(mapping_version),
+                        source_repo: Some(commit_syncer.get_source_repo_type()),
+                    }
+                } else {
+                    SyncedCommitMappingEntry {
+                        large_repo_id,
+                        small_repo_id,
+                        small_bcs_id: target_cs_id,
+                        large_bcs_id: source_cs_id,
+                        version_name: Some(mapping_version),
+                        source_repo: Some(commit_syncer.get_source_repo_type()),
+                    }
+                };
+
+                let res = mapping.add(&ctx, mapping_entry).await?;
+                if res {
+                    info!(
+                        ctx.logger(),
+                        "successfully inserted rewritten mapping entry"
+                    );
+                    Ok(())
+                } else {
+                    Err(anyhow!("failed to insert entry").into())
+                }
             }
         }
         (EQUIVALENT_WORKING_COPY_SUBCOMMAND, Some(sub_m)) => {
-            let (source_cs_id, target_cs_id, mapping_version) =
-                get_source_target_cs_ids_and_version(&ctx, sub_m, &commit_syncer).await?;
-            let small_repo_id = commit_syncer.get_small_repo().repo_identity().id();
-            let large_repo_id = commit_syncer.get_large_repo().repo_identity().id();
+            if let Some(path) = sub_m.value_of(FROM_FILE_ARG) {
+                // A small repo may have no equivalent working copy for a
+                // given large repo commit at all, so this bulk import
+                // supports both pair rows and not-sync-candidate-style
+                // source-only rows in the same file.
+                let (pair_lines, source_only_lines) =
+                    partition_from_file_lines(read_from_file_lines(path)?);
+
+                let (pair_rows, mut parse_failures) =
+                    resolve_pair_rows(&ctx, &commit_syncer, pair_lines).await?;
+                let (source_only_rows, source_only_parse_failures) =
+                    resolve_source_only_rows(&ctx, &commit_syncer, source_only_lines).await?;
+                parse_failures.extend(source_only_parse_failures);
+
+                let (pair_successes, pair_skipped, mut insert_failures) = bulk_insert_pair_rows(
+                    &ctx,
+                    &mapping,
+                    &commit_syncer,
+                    &source_repo,
+                    pair_rows,
+                    PairRowKind::EquivalentWorkingCopy,
+                )
+                .await?;
+                let (source_only_successes, source_only_skipped, source_only_insert_failures) =
+                    bulk_insert_source_only_rows(&ctx, &mapping, &commit_syncer, source_only_rows)
+                        .await?;
+                insert_failures.extend(source_only_insert_failures);
 
-            let mapping_entry = if small_repo_id == source_repo.repo_identity().id() {
-                EquivalentWorkingCopyEntry {
-                    large_repo_id,
-                    small_repo_id,
-                    small_bcs_id: Some(source_cs_id),
-                    large_bcs_id: target_cs_id,
-                    version_name: Some(mapping_version),
-                }
+                report_bulk_insert(
+                    &ctx,
+                    pair_successes + source_only_successes,
+                    pair_skipped + source_only_skipped,
+                    parse_failures,
+                    insert_failures,
+                )
             } else {
-                EquivalentWorkingCopyEntry {
-                    large_repo_id,
-                    small_repo_id,
-                    small_bcs_id: Some(target_cs_id),
-                    large_bcs_id: source_cs_id,
-                    version_name: Some(mapping_version),
-                }
-            };
+                let (source_cs_id, target_cs_id, mapping_version) =
+                    get_source_target_cs_ids_and_version(&ctx, sub_m, &commit_syncer).await?;
+                let small_repo_id = commit_syncer.get_small_repo().repo_identity().id();
+                let large_repo_id = commit_syncer.get_large_repo().repo_identity().id();
+
+                let mapping_entry = if small_repo_id == source_repo.repo_identity().id() {
+                    EquivalentWorkingCopyEntry {
+                        large_repo_id,
+                        small_repo_id,
+                        small_bcs_id: Some(source_cs_id),
+                        large_bcs_id: target_cs_id,
+                        version_name: Some(mapping_version),
+                    }
+                } else {
+                    EquivalentWorkingCopyEntry {
+                        large_repo_id,
+                        small_repo_id,
+                        small_bcs_id: Some(target_cs_id),
+                        large_bcs_id: source_cs_id,
+                        version_name: Some(mapping_version),
+                    }
+                };
 
-            let res = mapping
-                .insert_equivalent_working_copy(&ctx, mapping_entry)
-                .await?;
-            if res {
-                info!(
-                    ctx.logger(),
-                    "successfully inserted equivalent working copy"
-                );
-                Ok(())
-            } else {
-                Err(anyhow!("failed to insert entry").into())
+                let res = mapping
+                    .insert_equivalent_working_copy(&ctx, mapping_entry)
+                    .await?;
+                if res {
+                    info!(
+                        ctx.logger(),
+                        "successfully inserted equivalent working copy"
+                    );
+                    Ok(())
+                } else {
+                    Err(anyhow!("failed to insert entry").into())
+                }
             }
         }
+        (NOT_SYNC_CANDIDATE_SUBCOMMAND, Some(sub_m)) if sub_m.value_of(FROM_FILE_ARG).is_some() => {
+            let path = sub_m.value_of(FROM_FILE_ARG).unwrap();
+            let (rows, parse_failures) =
+                resolve_source_only_rows(&ctx, &commit_syncer, read_from_file_lines(path)?).await?;
+            let (successes, skipped, insert_failures) =
+                bulk_insert_source_only_rows(&ctx, &mapping, &commit_syncer, rows).await?;
+            report_bulk_insert(&ctx, successes, skipped, parse_failures, insert_failures)
+        }
         (NOT_SYNC_CANDIDATE_SUBCOMMAND, Some(sub_m)) => {
             let large_repo = commit_syncer.get_large_repo();
             let large_repo_hash = sub_m
@@ -852,6 +1048,329 @@ async fn get_source_target_cs_ids_and_version(
     Ok((source_cs_id, target_cs_id, mapping_version))
 }
 
+/// Which `insert` subcommand a `--from-file` row came from, and so which
+/// table/entry type it should be inserted as.
+#[derive(Copy, Clone)]
+enum PairRowKind {
+    Rewritten,
+    EquivalentWorkingCopy,
+}
+
+/// A single `source_hash<TAB>target_hash<TAB>mapping_version` row from a
+/// `--from-file` bulk import, resolved to changeset ids and a validated
+/// mapping version.
+struct PairRow {
+    line_no: usize,
+    source_cs_id: ChangesetId,
+    target_cs_id: ChangesetId,
+    mapping_version: CommitSyncConfigVersion,
+}
+
+/// Read a `--from-file` file into `(line_no, line)` pairs, trimming
+/// whitespace and dropping blank lines and `#`-comments. Lines are split
+/// into columns (on tabs or commas) later, by the `resolve_*` functions.
+fn read_from_file_lines(path: &str) -> Result<Vec<(usize, String)>, Error> {
+    let content = fs::read_to_string(path).with_context(|| format!("failed to read {}", path))?;
+    Ok(content
+        .lines()
+        .enumerate()
+        .map(|(idx, line)| (idx + 1, line.trim().to_string()))
+        .filter(|(_, line)| !line.is_empty() && !line.starts_with('#'))
+        .collect())
+}
+
+/// Split `--from-file` lines into pair rows (`source<TAB>target<TAB>version`,
+/// 3 columns) and source-only rows (`large_hash[, version]`, 1 or 2
+/// columns), so a single `equivalent-working-copy --from-file` invocation
+/// can bulk-import both shapes.
+fn partition_from_file_lines(
+    lines: Vec<(usize, String)>,
+) -> (Vec<(usize, String)>, Vec<(usize, String)>) {
+    lines
+        .into_iter()
+        .partition(|(_, line)| line.split(['\t', ',']).count() >= 3)
+}
+
+async fn resolve_pair_rows(
+    ctx: &CoreContext,
+    commit_syncer: &CommitSyncer<SqlSyncedCommitMapping, Repo>,
+    lines: Vec<(usize, String)>,
+) -> Result<(Vec<PairRow>, Vec<(usize, Error)>), Error> {
+    let mut rows = Vec::new();
+    let mut failures = Vec::new();
+
+    for (line_no, line) in lines {
+        let fields: Vec<&str> = line.split(['\t', ',']).map(str::trim).collect();
+        let result: Result<PairRow, Error> = async {
+            let (source_hash, target_hash, mapping_version) = match fields.as_slice() {
+                [source_hash, target_hash, mapping_version] => {
+                    (*source_hash, *target_hash, *mapping_version)
+                }
+                _ => {
+                    return Err(format_err!(
+                        "expected 3 columns (source_hash, target_hash, mapping_version), got {}",
+                        fields.len()
+                    ));
+                }
+            };
+
+            let mapping_version = CommitSyncConfigVersion(mapping_version.to_string());
+            if !commit_syncer.version_exists(&mapping_version).await? {
+                return Err(format_err!("{} version does not exist", mapping_version));
+            }
+
+            let (source_cs_id, target_cs_id) = try_join!(
+                helpers::csid_resolve(ctx, commit_syncer.get_source_repo(), source_hash),
+                helpers::csid_resolve(ctx, commit_syncer.get_target_repo(), target_hash),
+            )?;
+
+            Ok(PairRow {
+                line_no,
+                source_cs_id,
+                target_cs_id,
+                mapping_version,
+            })
+        }
+        .await;
+
+        match result {
+            Ok(row) => rows.push(row),
+            Err(e) => failures.push((line_no, e)),
+        }
+    }
+
+    Ok((rows, failures))
+}
+
+async fn bulk_insert_pair_rows(
+    ctx: &CoreContext,
+    mapping: &SqlSyncedCommitMapping,
+    commit_syncer: &CommitSyncer<SqlSyncedCommitMapping, Repo>,
+    source_repo: &Repo,
+    rows: Vec<PairRow>,
+    kind: PairRowKind,
+) -> Result<(usize, usize, Vec<(usize, Error)>), Error> {
+    let small_repo_id = commit_syncer.get_small_repo().repo_identity().id();
+    let large_repo_id = commit_syncer.get_large_repo().repo_identity().id();
+    let source_is_small = small_repo_id == source_repo.repo_identity().id();
+
+    let total = rows.len();
+    let mut successes = 0;
+    let mut skipped = 0;
+    let mut failures = Vec::new();
+
+    for chunk in rows.chunks(FROM_FILE_CHUNK_SIZE) {
+        for row in chunk {
+            let (small_bcs_id, large_bcs_id) = if source_is_small {
+                (row.source_cs_id, row.target_cs_id)
+            } else {
+                (row.target_cs_id, row.source_cs_id)
+            };
+
+            let res = match kind {
+                PairRowKind::Rewritten => {
+                    mapping
+                        .add(
+                            ctx,
+                            SyncedCommitMappingEntry {
+                                large_repo_id,
+                                small_repo_id,
+                                small_bcs_id,
+                                large_bcs_id,
+                                version_name: Some(row.mapping_version.clone()),
+                                source_repo: Some(commit_syncer.get_source_repo_type()),
+                            },
+                        )
+                        .await
+                }
+                PairRowKind::EquivalentWorkingCopy => {
+                    mapping
+                        .insert_equivalent_working_copy(
+                            ctx,
+                            EquivalentWorkingCopyEntry {
+                                large_repo_id,
+                                small_repo_id,
+                                small_bcs_id: Some(small_bcs_id),
+                                large_bcs_id,
+                                version_name: Some(row.mapping_version.clone()),
+                            },
+                        )
+                        .await
+                }
+            };
+
+            match res {
+                Ok(true) => successes += 1,
+                // The entry already exists, which is expected when resuming a
+                // rerun of a `--from-file` import that was only partially
+                // applied, so this isn't a failure.
+                Ok(false) => skipped += 1,
+                Err(e) => failures.push((row.line_no, e)),
+            }
+        }
+        info!(
+            ctx.logger(),
+            "processed {}/{} rows ({} succeeded, {} already present so far)",
+            successes + skipped + failures.len(),
+            total,
+            successes,
+            skipped,
+        );
+    }
+
+    Ok((successes, skipped, failures))
+}
+
+/// A single source-only `--from-file` row for `not-sync-candidate`: a large
+/// repo hash and an optional mapping version.
+struct SourceOnlyRow {
+    line_no: usize,
+    cs_id: ChangesetId,
+    mapping_version: Option<CommitSyncConfigVersion>,
+}
+
+async fn resolve_source_only_rows(
+    ctx: &CoreContext,
+    commit_syncer: &CommitSyncer<SqlSyncedCommitMapping, Repo>,
+    lines: Vec<(usize, String)>,
+) -> Result<(Vec<SourceOnlyRow>, Vec<(usize, Error)>), Error> {
+    let large_repo = commit_syncer.get_large_repo();
+    let mut rows = Vec::new();
+    let mut failures = Vec::new();
+
+    for (line_no, line) in lines {
+        let fields: Vec<&str> = line.split(['\t', ',']).map(str::trim).collect();
+        let result: Result<SourceOnlyRow, Error> = async {
+            let (hash, mapping_version) = match fields.as_slice() {
+                [hash] => (*hash, None),
+                [hash, mapping_version] => (*hash, Some(*mapping_version)),
+                _ => {
+                    return Err(format_err!(
+                        "expected 1 or 2 columns (large_repo_hash[, mapping_version]), got {}",
+                        fields.len()
+                    ));
+                }
+            };
+
+            let cs_id = helpers::csid_resolve(ctx, large_repo, hash).await?;
+            let mapping_version = match mapping_version {
+                Some(mapping_version) => {
+                    let mapping_version = CommitSyncConfigVersion(mapping_version.to_string());
+                    if !commit_syncer.version_exists(&mapping_version).await? {
+                        return Err(format_err!("{} version does not exist", mapping_version));
+                    }
+                    Some(mapping_version)
+                }
+                None => None,
+            };
+
+            Ok(SourceOnlyRow {
+                line_no,
+                cs_id,
+                mapping_version,
+            })
+        }
+        .await;
+
+        match result {
+            Ok(row) => rows.push(row),
+            Err(e) => failures.push((line_no, e)),
+        }
+    }
+
+    Ok((rows, failures))
+}
+
+async fn bulk_insert_source_only_rows(
+    ctx: &CoreContext,
+    mapping: &SqlSyncedCommitMapping,
+    commit_syncer: &CommitSyncer<SqlSyncedCommitMapping, Repo>,
+    rows: Vec<SourceOnlyRow>,
+) -> Result<(usize, usize, Vec<(usize, Error)>), Error> {
+    let small_repo_id = commit_syncer.get_small_repo().repo_identity().id();
+    let large_repo_id = commit_syncer.get_large_repo().repo_identity().id();
+
+    let total = rows.len();
+    let mut successes = 0;
+    let mut skipped = 0;
+    let mut failures = Vec::new();
+
+    for chunk in rows.chunks(FROM_FILE_CHUNK_SIZE) {
+        for row in chunk {
+            let res = mapping
+                .insert_equivalent_working_copy(
+                    ctx,
+                    EquivalentWorkingCopyEntry {
+                        large_repo_id,
+                        small_repo_id,
+                        small_bcs_id: None,
+                        large_bcs_id: row.cs_id,
+                        version_name: row.mapping_version.clone(),
+                    },
+                )
+                .await;
+
+            match res {
+                Ok(true) => successes += 1,
+                // The entry already exists, which is expected when resuming a
+                // rerun of a `--from-file` import that was only partially
+                // applied, so this isn't a failure.
+                Ok(false) => skipped += 1,
+                Err(e) => failures.push((row.line_no, e)),
+            }
+        }
+        info!(
+            ctx.logger(),
+            "processed {}/{} rows ({} succeeded, {} already present so far)",
+            successes + skipped + failures.len(),
+            total,
+            successes,
+            skipped,
+        );
+    }
+
+    Ok((successes, skipped, failures))
+}
+
+/// Print a per-row success/failure report for a `--from-file` bulk import and
+/// turn any failures into the command's overall error result. Rows that were
+/// already present (an idempotent no-op when resuming a partially-applied
+/// import) are reported as skipped, not as failures.
+fn report_bulk_insert(
+    ctx: &CoreContext,
+    successes: usize,
+    skipped: usize,
+    parse_failures: Vec<(usize, Error)>,
+    insert_failures: Vec<(usize, Error)>,
+) -> Result<(), SubcommandError> {
+    let total_failures = parse_failures.len() + insert_failures.len();
+
+    for (line_no, e) in &parse_failures {
+        warn!(ctx.logger(), "line {}: failed to parse: {:#}", line_no, e);
+    }
+    for (line_no, e) in &insert_failures {
+        warn!(ctx.logger(), "line {}: failed to insert: {:#}", line_no, e);
+    }
+
+    info!(
+        ctx.logger(),
+        "bulk import finished: {} succeeded, {} already present, {} failed",
+        successes,
+        skipped,
+        total_failures
+    );
+
+    if total_failures > 0 {
+        Err(format_err!(
+            "{} row(s) failed during bulk import (see warnings above)",
+            total_failures
+        )
+        .into())
+    } else {
+        Ok(())
+    }
+}
+
 struct MappingCommitOptions {
     add_mapping_change_extra: bool,
     // Fine to have Option<NonRootMPath> in this case since this represents an Optional
@@ -1108,15 +1627,79 @@ fn print_commit_sync_config(csc: CommitSyncConfig, line_prefix: &str) {
     }
 }
 
+/// Structured (tagged) JSON for `DefaultSmallToLargeCommitSyncPathAction`,
+/// in place of a `{:?}` Debug blob, so callers can match on `"type"` without
+/// depending on the Debug impl's exact wording.
+fn default_action_to_json(action: &DefaultSmallToLargeCommitSyncPathAction) -> serde_json::Value {
+    match action {
+        DefaultSmallToLargeCommitSyncPathAction::Preserve => serde_json::json!({
+            "type": "preserve",
+        }),
+        DefaultSmallToLargeCommitSyncPathAction::PrependPrefix(prefix) => serde_json::json!({
+            "type": "prepend_prefix",
+            "prefix": prefix.to_string(),
+        }),
+    }
+}
+
+fn commit_sync_config_to_json(csc: &CommitSyncConfig) -> serde_json::Value {
+    let small_repos: serde_json::Map<_, _> = csc
+        .small_repos
+        .iter()
+        .sorted_by_key(|(small_repo_id, _)| **small_repo_id)
+        .map(|(small_repo_id, small_repo_config)| {
+            let prefix_map: serde_json::Map<_, _> = small_repo_config
+                .map
+                .iter()
+                .sorted_by_key(|(from, _)| (*from).clone())
+                .map(|(from, to)| (from.to_string(), serde_json::Value::String(to.to_string())))
+                .collect();
+            (
+                small_repo_id.to_string(),
+                serde_json::json!({
+                    "default_action": default_action_to_json(&small_repo_config.default_action),
+                    "map": prefix_map,
+                }),
+            )
+        })
+        .collect();
+    serde_json::json!({
+        "large_repo_id": csc.large_repo_id.id(),
+        "common_pushrebase_bookmarks": csc.common_pushrebase_bookmarks,
+        "version_name": csc.version_name.0,
+        "small_repos": small_repos,
+    })
+}
+
 async fn subcommand_list<'a, L: LiveCommitSyncConfig>(
     repo_id: RepositoryId,
     live_commit_sync_config: L,
     with_contents: bool,
+    output: OutputFormat,
 ) -> Result<(), Error> {
     let all = live_commit_sync_config
         .get_all_commit_sync_config_versions(repo_id)
         .await?;
-    for (version_name, csc) in all.into_iter().sorted_by_key(|(vn, _)| vn.clone()) {
+    let all = all.into_iter().sorted_by_key(|(vn, _)| vn.clone());
+
+    if output.is_json() {
+        let versions: serde_json::Value = if with_contents {
+            all.map(|(version_name, csc)| {
+                serde_json::json!({
+                    "version_name": version_name,
+                    "config": commit_sync_config_to_json(&csc),
+                })
+            })
+            .collect()
+        } else {
+            all.map(|(version_name, _)| serde_json::Value::String(version_name))
+                .collect()
+        };
+        println!("{}", serde_json::to_string_pretty(&versions)?);
+        return Ok(());
+    }
+
+    for (version_name, csc) in all {
         if with_contents {
             println!("{}:", version_name);
             print_commit_sync_config(csc, "  ");
@@ -1133,18 +1716,96 @@ async fn subcommand_by_version<'a, L: LiveCommitSyncConfig>(
     repo_id: RepositoryId,
     live_commit_sync_config: L,
     version_name: String,
+    output: OutputFormat,
 ) -> Result<(), Error> {
     let csc = live_commit_sync_config
         .get_commit_sync_config_by_version(repo_id, &CommitSyncConfigVersion(version_name))
         .await?;
-    print_commit_sync_config(csc, "");
+    if output.is_json() {
+        println!(
+            "{}",
+            serde_json::to_string_pretty(&commit_sync_config_to_json(&csc))?
+        );
+    } else {
+        print_commit_sync_config(csc, "");
+    }
     Ok(())
 }
 
+/// Default bounded concurrency for `verify-wc --to-hash` range verification, used
+/// when `LIMIT_ARG` isn't given.
+const DEFAULT_VERIFY_WC_RANGE_CONCURRENCY: usize = 10;
+/// How many mismatching paths to print per failing commit, so a large range
+/// doesn't flood the terminal with a single commit's diff.
+const MAX_MISMATCHES_TO_PRINT: usize = 10;
+
+async fn subcommand_verify_wc_range(
+    ctx: CoreContext,
+    commit_syncer: CommitSyncer<SqlSyncedCommitMapping, Repo>,
+    from_hash: ChangesetId,
+    to_hash: ChangesetId,
+    concurrency: usize,
+) -> Result<(), SubcommandError> {
+    let large_repo = commit_syncer.get_large_repo();
+    let mut cs_ids = large_repo
+        .commit_graph()
+        .ancestors_difference(&ctx, vec![to_hash], vec![from_hash])
+        .await?;
+    // `ancestors_difference` returns commits newest-first; verify oldest-first so
+    // that a failure early in the range is reported before later, possibly
+    // derived, failures.
+    cs_ids.reverse();
+
+    info!(
+        ctx.logger(),
+        "verifying working copy for {} commits in range",
+        cs_ids.len()
+    );
+
+    let live_commit_sync_config = commit_syncer.live_commit_sync_config.clone();
+    let commit_syncer = &commit_syncer;
+    let ctx = &ctx;
+    let results: Vec<_> = stream::iter(cs_ids.into_iter().map(|cs_id| {
+        let live_commit_sync_config = live_commit_sync_config.clone();
+        async move {
+            let res = verify_working_copy(ctx, commit_syncer, cs_id, live_commit_sync_config).await;
+            (cs_id, res)
+        }
+    }))
+    .buffered(concurrency)
+    .collect()
+    .await;
+
+    let mut failures = 0;
+    for (cs_id, res) in results {
+        if let Err(e) = res {
+            failures += 1;
+            let mismatches = format!("{:#}", e)
+                .lines()
+                .take(MAX_MISMATCHES_TO_PRINT)
+                .map(|line| format!("    {}", line))
+                .collect::<Vec<_>>()
+                .join("\n");
+            warn!(
+                ctx.logger(),
+                "{}: working copy verification failed:\n{}", cs_id, mismatches
+            );
+        }
+    }
+
+    if failures > 0 {
+        Err(format_err!("{} commits failed working copy verification", failures).into())
+    } else {
+        info!(ctx.logger(), "all is well!");
+        Ok(())
+    }
+}
+
 async fn subcommand_map(
     ctx: CoreContext,
     commit_syncer: CommitSyncer<SqlSyncedCommitMapping, Repo>,
     hash: String,
+    output: OutputFormat,
 ) -> Result<(), SubcommandError> {
     let source_repo = commit_syncer.get_source_repo();
     let source_cs_id = helpers::csid_resolve(&ctx, source_repo, &hash).await?;
@@ -1152,6 +1813,21 @@ async fn subcommand_map(
     let plural_commit_sync_outcome = commit_syncer
         .get_plural_commit_sync_outcome(&ctx, source_cs_id)
         .await?;
+    if output.is_json() {
+        let json = match &plural_commit_sync_outcome {
+            Some(plural_commit_sync_outcome) => serde_json::json!({
+                "hash": hash,
+                "remapped": true,
+                "outcome": plural_commit_sync_outcome_to_json(plural_commit_sync_outcome),
+            }),
+            None => serde_json::json!({
+                "hash": hash,
+                "remapped": false,
+            }),
+        };
+        println!("{}", serde_json::to_string_pretty(&json)?);
+        return Ok(());
+    }
     match plural_commit_sync_outcome {
         Some(plural_commit_sync_outcome) => {
             println!("{:?}", plural_commit_sync_outcome);
@@ -1164,6 +1840,60 @@ async fn subcommand_map(
     Ok(())
 }
 
+/// Structured (tagged) JSON for `PluralCommitSyncOutcome`, in place of a
+/// `{:?}` Debug blob, so callers can match on `"kind"` without depending on
+/// the Debug impl's exact wording.
+fn plural_commit_sync_outcome_to_json(outcome: &PluralCommitSyncOutcome) -> serde_json::Value {
+    use PluralCommitSyncOutcome::*;
+    match outcome {
+        NotSyncCandidate(..) => serde_json::json!({
+            "kind": "not_sync_candidate",
+        }),
+        EquivalentWorkingCopyAncestor(large_cs_id, ..) => serde_json::json!({
+            "kind": "equivalent_working_copy_ancestor",
+            "large_cs_id": large_cs_id.to_string(),
+        }),
+        RewrittenAs(rewritten_commits) => serde_json::json!({
+            "kind": "rewritten_as",
+            "rewritten_as": rewritten_commits
+                .iter()
+                .map(|(cs_id, version)| serde_json::json!({
+                    "cs_id": cs_id.to_string(),
+                    "version_name": version.0.clone(),
+                }))
+                .collect::<Vec<_>>(),
+        }),
+    }
+}
+
+fn bookmark_diff_to_json(diff: &BookmarkDiff) -> serde_json::Value {
+    use BookmarkDiff::*;
+    match diff {
+        InconsistentValue {
+            target_bookmark,
+            target_cs_id,
+            source_cs_id,
+        } => serde_json::json!({
+            "kind": "inconsistent_value",
+            "target_bookmark": target_bookmark.to_string(),
+            "target_cs_id": target_cs_id.to_string(),
+            "source_cs_id": source_cs_id.map(|id| id.to_string()),
+        }),
+        MissingInTarget {
+            target_bookmark,
+            source_cs_id,
+        } => serde_json::json!({
+            "kind": "missing_in_target",
+            "target_bookmark": target_bookmark.to_string(),
+            "source_cs_id": source_cs_id.to_string(),
+        }),
+        NoSyncOutcome { target_bookmark } => serde_json::json!({
+            "kind": "no_sync_outcome",
+            "target_bookmark": target_bookmark.to_string(),
+        }),
+    }
+}
+
 async fn subcommand_verify_bookmarks(
     ctx: CoreContext,
     source_repo: Repo,
@@ -1172,6 +1902,7 @@ async fn subcommand_verify_bookmarks(
     run_mode: VerifyRunMode,
     live_commit_sync_config: Arc<dyn LiveCommitSyncConfig>,
     matches: &MononokeMatches<'_>,
+    output: OutputFormat,
 ) -> Result<(), SubcommandError> {
     let common_config =
         live_commit_sync_config.get_common_config(target_repo.repo_identity().id())?;
@@ -1187,18 +1918,49 @@ async fn subcommand_verify_bookmarks(
 
     let diff = find_bookmark_diff(ctx.clone(), &syncers.large_to_small).await?;
 
+    // Emit the JSON diff up front, regardless of run mode, so tooling can
+    // gate on it whether we're just reporting, dry-running
+    // (`--no-bookmark-updates`), or actually updating large repo bookmarks.
+    if output.is_json() {
+        let json = serde_json::json!({
+            "diff": diff.iter().map(bookmark_diff_to_json).collect::<Vec<_>>(),
+        });
+        println!("{}", serde_json::to_string_pretty(&json)?);
+    }
+
     if diff.is_empty() {
-        info!(ctx.logger(), "all is well!");
+        if !output.is_json() {
+            info!(ctx.logger(), "all is well!");
+        }
         return Ok(());
     }
 
     match run_mode {
-        VerifyRunMode::UpdateLargeRepoBookmarks { mode, limit } => {
-            update_large_repo_bookmarks(ctx.clone(), &diff, &syncers, &common_config, mode, limit)
-                .await?;
+        VerifyRunMode::UpdateLargeRepoBookmarks {
+            mode,
+            limit,
+            reset_checkpoint,
+            concurrency,
+            start_after,
+        } => {
+            update_large_repo_bookmarks(
+                ctx.clone(),
+                &diff,
+                &syncers,
+                &common_config,
+                mode,
+                limit,
+                reset_checkpoint,
+                concurrency,
+                start_after,
+            )
+            .await?;
             Ok(())
         }
         VerifyRunMode::JustVerify => {
+            if output.is_json() {
+                return Err(format_err!("found {} inconsistencies", diff.len()).into());
+            }
             for d in &diff {
                 use BookmarkDiff::*;
                 match d {
@@ -1248,6 +2010,232 @@ async fn subcommand_verify_bookmarks(
     }
 }
 
+/// `mutable_counters` key prefix used to persist the name of the last
+/// `target_bookmark` (in sort order) that a `verify-bookmarks
+/// --update-large-repo-bookmarks` run has reconciled, so a rerun can resume
+/// instead of reapplying the whole diff from scratch. This is a bookmark
+/// name rather than a count: the diff is recomputed (and shrinks) on every
+/// run, so a positional index into one run's diff is meaningless against
+/// another's, while a name can always be relocated in a freshly sorted diff
+/// via `partition_point`.
+///
+/// `mutable_counters` only stores `i64`s, so the name is packed 8 bytes at a
+/// time across `CHECKPOINT_NAME_MAX_CHUNKS` counters (plus one more counter
+/// for its byte length) under keys derived from this prefix, rather than
+/// written as a single blob to a blobstore: unlike `mutable_counters`,
+/// blobstores aren't guaranteed to support overwriting an existing key, so a
+/// rerun's write to resume from could silently fail to take effect.
+fn format_verify_bookmarks_checkpoint_key(
+    small_repo_id: RepositoryId,
+    large_repo_id: RepositoryId,
+) -> String {
+    format!(
+        "xrepo_sync.verify_bookmarks_checkpoint.{}.{}",
+        small_repo_id, large_repo_id
+    )
+}
+
+/// How many 8-byte `mutable_counters` chunks a checkpoint bookmark name may
+/// be packed across. 128 bytes comfortably covers any bookmark name seen in
+/// practice; a longer name fails loudly instead of being silently truncated.
+const CHECKPOINT_NAME_MAX_CHUNKS: usize = 16;
+
+fn checkpoint_len_counter(checkpoint_key: &str) -> String {
+    format!("{}.len", checkpoint_key)
+}
+
+fn checkpoint_chunk_counter(checkpoint_key: &str, chunk_idx: usize) -> String {
+    format!("{}.chunk.{}", checkpoint_key, chunk_idx)
+}
+
+/// Read the last reconciled bookmark name persisted by a previous
+/// `--update-large-repo-bookmarks` run, if any.
+async fn get_verify_bookmarks_checkpoint(
+    ctx: &CoreContext,
+    small_repo: &Repo,
+    checkpoint_key: &str,
+) -> Result<Option<String>, Error> {
+    let len = match small_repo
+        .mutable_counters()
+        .get_counter(ctx, &checkpoint_len_counter(checkpoint_key))
+        .await?
+    {
+        Some(len) => usize::try_from(len).with_context(|| format!("invalid checkpoint length {}", len))?,
+        None => return Ok(None),
+    };
+
+    let mut bytes = Vec::with_capacity(len);
+    for chunk_idx in 0..len.div_ceil(8) {
+        let chunk = small_repo
+            .mutable_counters()
+            .get_counter(ctx, &checkpoint_chunk_counter(checkpoint_key, chunk_idx))
+            .await?
+            .with_context(|| format!("missing checkpoint chunk {}", chunk_idx))?;
+        bytes.extend_from_slice(&chunk.to_be_bytes());
+    }
+    bytes.truncate(len);
+
+    Ok(Some(String::from_utf8(bytes)?))
+}
+
+/// Persist `last_bookmark` as the checkpoint to resume after on the next
+/// `--update-large-repo-bookmarks` run.
+async fn set_verify_bookmarks_checkpoint(
+    ctx: &CoreContext,
+    small_repo: &Repo,
+    checkpoint_key: &str,
+    last_bookmark: &BookmarkKey,
+) -> Result<(), Error> {
+    let name = last_bookmark.to_string();
+    let name_bytes = name.as_bytes();
+    let num_chunks = name_bytes.len().div_ceil(8).max(1);
+    if num_chunks > CHECKPOINT_NAME_MAX_CHUNKS {
+        return Err(anyhow!(
+            "bookmark name {} is too long to checkpoint ({} bytes, max {})",
+            name,
+            name_bytes.len(),
+            CHECKPOINT_NAME_MAX_CHUNKS * 8,
+        ));
+    }
+
+    for chunk_idx in 0..num_chunks {
+        let mut chunk = [0u8; 8];
+        let start = chunk_idx * 8;
+        let end = (start + 8).min(name_bytes.len());
+        chunk[..end - start].copy_from_slice(&name_bytes[start..end]);
+        let res = small_repo
+            .mutable_counters()
+            .set_counter(
+                ctx,
+                &checkpoint_chunk_counter(checkpoint_key, chunk_idx),
+                i64::from_be_bytes(chunk),
+                None, // prev_value
+            )
+            .await?;
+        if !res {
+            return Err(anyhow!("failed to set checkpoint chunk {} counter", chunk_idx));
+        }
+    }
+    let res = small_repo
+        .mutable_counters()
+        .set_counter(
+            ctx,
+            &checkpoint_len_counter(checkpoint_key),
+            name_bytes.len() as i64,
+            None, // prev_value
+        )
+        .await?;
+    if !res {
+        return Err(anyhow!("failed to set checkpoint length counter"));
+    }
+
+    Ok(())
+}
+
+/// The concrete large-repo bookmark mutation to apply for one `BookmarkDiff`,
+/// already resolved against the commit mapping (and with data derived, for a
+/// `Set`) so that applying it to a transaction can't fail.
+enum ResolvedBookmarkUpdate {
+    Set(BookmarkKey, ChangesetId),
+    Delete(BookmarkKey),
+    Skip,
+}
+
+/// Resolve a single `BookmarkDiff` against the small-to-large commit mapping,
+/// deriving data for the remapped target changeset if one is found. Doesn't
+/// touch any bookmarks itself, so many of these can run concurrently.
+async fn resolve_bookmark_update(
+    ctx: &CoreContext,
+    d: &BookmarkDiff,
+    syncers: &Syncers<SqlSyncedCommitMapping, Repo>,
+    large_repo: &Repo,
+    bookmark_renamer: &(impl Fn(&BookmarkKey) -> Option<BookmarkKey> + Sync),
+) -> Result<ResolvedBookmarkUpdate, Error> {
+    use BookmarkDiff::*;
+    match d {
+        InconsistentValue {
+            target_bookmark,
+            target_cs_id,
+            ..
+        } => {
+            let outcomes = syncers
+                .small_to_large
+                .get_plural_commit_sync_outcome(ctx, *target_cs_id)
+                .await?
+                .with_context(|| format!("Missing outcome for {} from small repo", target_cs_id))?;
+
+            use PluralCommitSyncOutcome::*;
+            let new_value = match outcomes {
+                NotSyncCandidate(..) => {
+                    warn!(
+                        ctx.logger(),
+                        "{} from small repo doesn't remap to large repo", target_cs_id,
+                    );
+                    None
+                }
+                EquivalentWorkingCopyAncestor(large_cs_id, _) => Some(large_cs_id),
+                RewrittenAs(rewritten_commits) if rewritten_commits.len() == 1 => {
+                    Some(rewritten_commits.into_iter().next().unwrap().0)
+                }
+                RewrittenAs(rewritten_commits) => {
+                    return Err(format_err!(
+                        "multiple remappings of {} in {}: {:?}",
+                        *target_cs_id,
+                        large_repo.repo_identity().name(),
+                        rewritten_commits,
+                    ));
+                }
+            };
+
+            let large_cs_id = match new_value {
+                Some(large_cs_id) => large_cs_id,
+                None => return Ok(ResolvedBookmarkUpdate::Skip),
+            };
+
+            let derived_data_types = large_repo
+                .repo_derived_data()
+                .active_config()
+                .types
+                .iter()
+                .copied()
+                .collect::<Vec<_>>();
+            large_repo
+                .repo_derived_data()
+                .manager()
+                .derive_bulk(ctx, &[large_cs_id], None, &derived_data_types, None)
+                .await?;
+            let large_bookmark = bookmark_renamer(target_bookmark).ok_or_else(|| {
+                format_err!("small bookmark {} remaps to nothing", target_bookmark)
+            })?;
+
+            info!(ctx.logger(), "setting {} {}", large_bookmark, large_cs_id);
+            Ok(ResolvedBookmarkUpdate::Set(large_bookmark, large_cs_id))
+        }
+        MissingInTarget {
+            target_bookmark, ..
+        } => {
+            warn!(
+                ctx.logger(),
+                "large repo bookmark (renames to {}) not found in small repo", target_bookmark,
+            );
+            let large_bookmark = bookmark_renamer(target_bookmark).ok_or_else(|| {
+                format_err!("small bookmark {} remaps to nothing", target_bookmark)
+            })?;
+            info!(ctx.logger(), "deleting {}", large_bookmark);
+            Ok(ResolvedBookmarkUpdate::Delete(large_bookmark))
+        }
+        NoSyncOutcome { target_bookmark } => {
+            warn!(
+                ctx.logger(),
+                "Not updating {} because it points to a commit that has no \
+                 equivalent in source repo.",
+                target_bookmark,
+            );
+            Ok(ResolvedBookmarkUpdate::Skip)
+        }
+    }
+}
+
 async fn update_large_repo_bookmarks(
     ctx: CoreContext,
     diff: &[BookmarkDiff],
@@ -1255,134 +2243,196 @@ async fn update_large_repo_bookmarks(
     common_commit_sync_config: &CommonCommitSyncConfig,
     update_mode: UpdateLargeRepoBookmarksMode,
     limit: Option<usize>,
+    reset_checkpoint: bool,
+    concurrency: usize,
+    start_after: Option<String>,
 ) -> Result<(), Error> {
     let large_repo = syncers.small_to_large.get_large_repo();
-    let mut book_txn = large_repo.bookmarks().create_transaction(ctx.clone());
+    let small_repo = syncers.small_to_large.get_small_repo();
 
     let bookmark_renamer = syncers.small_to_large.get_bookmark_renamer().await?;
 
-    let diff: Box<dyn Iterator<Item = &BookmarkDiff>> = match limit {
+    let checkpoint_key = format_verify_bookmarks_checkpoint_key(
+        small_repo.repo_identity().id(),
+        large_repo.repo_identity().id(),
+    );
+
+    // `diff`'s order isn't guaranteed to be stable across runs, so sort by
+    // bookmark name to give both the persisted checkpoint and `--start-after`
+    // a stable meaning.
+    let mut sorted_diff: Vec<&BookmarkDiff> = diff.iter().collect();
+    sorted_diff.sort_by_key(|d| d.target_bookmark().to_string());
+
+    // `--start-after` is an explicit, caller-supplied cursor that lets an
+    // operator page through the diff by bookmark name across invocations
+    // (e.g. running several chunks in parallel), bypassing the persisted
+    // checkpoint for this one invocation.
+    let already_done = match &start_after {
+        Some(start_after) => {
+            let idx = sorted_diff.partition_point(|d| d.target_bookmark().to_string().as_str() <= start_after.as_str());
+            info!(
+                ctx.logger(),
+                "resuming after {}: {}/{} bookmark(s) skipped", start_after, idx, sorted_diff.len(),
+            );
+            idx
+        }
+        None if reset_checkpoint => {
+            info!(
+                ctx.logger(),
+                "ignoring checkpoint {}, reconciling the whole diff from scratch", checkpoint_key
+            );
+            0
+        }
+        None => {
+            // The checkpoint is the *name* of the last reconciled bookmark,
+            // not a position: `diff` is recomputed (and shrinks) on every
+            // run, so a count taken against a previous, larger diff would be
+            // meaningless here. Relocate the name in this run's sorted diff
+            // exactly as `--start-after` does.
+            match get_verify_bookmarks_checkpoint(&ctx, small_repo, &checkpoint_key).await? {
+                Some(checkpoint) => {
+                    let idx = sorted_diff
+                        .partition_point(|d| d.target_bookmark().to_string().as_str() <= checkpoint.as_str());
+                    info!(
+                        ctx.logger(),
+                        "checkpoint {}: resuming after {}, {}/{} bookmark(s) already reconciled",
+                        checkpoint_key,
+                        checkpoint,
+                        idx,
+                        sorted_diff.len(),
+                    );
+                    idx
+                }
+                None => {
+                    info!(
+                        ctx.logger(),
+                        "checkpoint {}: no checkpoint found, reconciling the whole diff", checkpoint_key
+                    );
+                    0
+                }
+            }
+        }
+    };
+    let remaining = &sorted_diff[already_done..];
+
+    let to_process = match limit {
         Some(limit) => {
             warn!(
                 ctx.logger(),
-                "found {} inconsistencies, will update at most {} of them...",
-                diff.len(),
+                "found {} remaining inconsistencies, will update at most {} of them...",
+                remaining.len(),
                 limit
             );
-            Box::new(diff.iter().take(limit))
+            &remaining[..limit.min(remaining.len())]
         }
         None => {
             warn!(
                 ctx.logger(),
-                "found {} inconsistencies, trying to update them...",
-                diff.len()
+                "found {} remaining inconsistencies, trying to update them...",
+                remaining.len()
             );
-            Box::new(diff.iter())
+            remaining
         }
     };
-    for d in diff {
-        if common_commit_sync_config
-            .common_pushrebase_bookmarks
-            .contains(d.target_bookmark())
-        {
-            info!(
-                ctx.logger(),
-                "skipping {} because it's a common bookmark",
-                d.target_bookmark()
-            );
-            continue;
-        }
 
-        use BookmarkDiff::*;
-        match d {
-            InconsistentValue {
-                target_bookmark,
-                target_cs_id,
-                ..
-            } => {
-                let outcomes = syncers
-                    .small_to_large
-                    .get_plural_commit_sync_outcome(&ctx, *target_cs_id)
-                    .await?
-                    .with_context(|| {
-                        format!("Missing outcome for {} from small repo", target_cs_id)
-                    })?;
-
-                use PluralCommitSyncOutcome::*;
-                let new_value = match outcomes {
-                    NotSyncCandidate(..) => {
-                        warn!(
-                            ctx.logger(),
-                            "{} from small repo doesn't remap to large repo", target_cs_id,
-                        );
-                        None
-                    }
-                    EquivalentWorkingCopyAncestor(large_cs_id, _) => Some(large_cs_id),
-                    RewrittenAs(rewritten_commits) if rewritten_commits.len() == 1 => {
-                        Some(rewritten_commits.into_iter().next().unwrap().0)
-                    }
-                    RewrittenAs(rewritten_commits) => {
-                        return Err(format_err!(
-                            "multiple remappings of {} in {}: {:?}",
-                            *target_cs_id,
-                            large_repo.repo_identity().name(),
-                            rewritten_commits,
-                        ));
-                    }
-                };
-
-                if let Some(large_cs_id) = new_value {
-                    let derived_data_types = large_repo
-                        .repo_derived_data()
-                        .active_config()
-                        .types
-                        .iter()
-                        .copied()
-                        .collect::<Vec<_>>();
-                    large_repo
-                        .repo_derived_data()
-                        .manager()
-                        .derive_bulk(&ctx, &[large_cs_id], None, &derived_data_types, None)
-                        .await?;
-                    let reason = BookmarkUpdateReason::XRepoSync;
-                    let large_bookmark = bookmark_renamer(target_bookmark).ok_or_else(|| {
-                        format_err!("small bookmark {} remaps to nothing", target_bookmark)
-                    })?;
-
-                    info!(ctx.logger(), "setting {} {}", large_bookmark, large_cs_id);
-                    if update_mode == UpdateLargeRepoBookmarksMode::Real {
-                        book_txn.force_set(&large_bookmark, large_cs_id, reason)?;
-                    }
+    // Derive data for and resolve the commit mapping of every diff entry
+    // concurrently (bounded by `concurrency`) before touching any bookmarks.
+    // Common pushrebase bookmarks are filtered out up front since they're
+    // never mutated and don't need resolving.
+    let resolved: Vec<Result<ResolvedBookmarkUpdate, Error>> = {
+        let ctx = &ctx;
+        let bookmark_renamer = &bookmark_renamer;
+        stream::iter(to_process.iter().map(|d| {
+            let d: &BookmarkDiff = *d;
+            async move {
+                if common_commit_sync_config
+                    .common_pushrebase_bookmarks
+                    .contains(d.target_bookmark())
+                {
+                    info!(
+                        ctx.logger(),
+                        "skipping {} because it's a common bookmark",
+                        d.target_bookmark()
+                    );
+                    return Ok(ResolvedBookmarkUpdate::Skip);
                 }
+                resolve_bookmark_update(ctx, d, syncers, large_repo, bookmark_renamer).await
             }
-            MissingInTarget {
-                target_bookmark, ..
-            } => {
-                warn!(
-                    ctx.logger(),
-                    "large repo bookmark (renames to {}) not found in small repo", target_bookmark,
-                );
-                let large_bookmark = bookmark_renamer(target_bookmark).ok_or_else(|| {
-                    format_err!("small bookmark {} remaps to nothing", target_bookmark)
-                })?;
-                let reason = BookmarkUpdateReason::XRepoSync;
-                info!(ctx.logger(), "deleting {}", large_bookmark);
-                if update_mode == UpdateLargeRepoBookmarksMode::Real {
+        }))
+        .buffered(concurrency)
+        .collect()
+        .await
+    };
+
+    // Flush the resolved mutations in batches of `concurrency`, each its own
+    // committed transaction, so a large diff doesn't require one giant
+    // transaction and so the checkpoint only advances past batches that have
+    // actually been persisted.
+    let reason = BookmarkUpdateReason::XRepoSync;
+    let mut processed = already_done;
+    let mut book_txn = large_repo.bookmarks().create_transaction(ctx.clone());
+    let mut pending_in_batch = 0;
+    let mut last_in_batch = None;
+    for (d, resolved) in to_process.iter().zip(resolved) {
+        let resolved = resolved?;
+        processed += 1;
+        pending_in_batch += 1;
+        last_in_batch = Some(d.target_bookmark());
+
+        if update_mode == UpdateLargeRepoBookmarksMode::Real {
+            match resolved {
+                ResolvedBookmarkUpdate::Set(large_bookmark, large_cs_id) => {
+                    book_txn.force_set(&large_bookmark, large_cs_id, reason)?;
+                }
+                ResolvedBookmarkUpdate::Delete(large_bookmark) => {
                     book_txn.force_delete(&large_bookmark, reason)?;
                 }
+                ResolvedBookmarkUpdate::Skip => {}
             }
-            NoSyncOutcome { target_bookmark } => {
-                warn!(
-                    ctx.logger(),
-                    "Not updating {} because it points to a commit that has no \
-                     equivalent in source repo.",
-                    target_bookmark,
-                );
+        }
+
+        if pending_in_batch >= concurrency {
+            if update_mode == UpdateLargeRepoBookmarksMode::Real {
+                book_txn.commit().await?;
+                if let Some(last) = last_in_batch {
+                    set_verify_bookmarks_checkpoint(&ctx, small_repo, &checkpoint_key, last)
+                        .await?;
+                }
+                book_txn = large_repo.bookmarks().create_transaction(ctx.clone());
             }
+            pending_in_batch = 0;
+        }
+    }
+
+    if update_mode == UpdateLargeRepoBookmarksMode::Real && pending_in_batch > 0 {
+        book_txn.commit().await?;
+        if let Some(last) = last_in_batch {
+            set_verify_bookmarks_checkpoint(&ctx, small_repo, &checkpoint_key, last).await?;
         }
     }
 
-    book_txn.commit().await?;
+    info!(
+        ctx.logger(),
+        "checkpoint {}: {}/{} bookmark(s) reconciled ({:?})",
+        checkpoint_key,
+        processed,
+        sorted_diff.len(),
+        update_mode,
+    );
+
+    // Log (never print to stdout, which in `--output json` mode carries only
+    // the machine-readable diff) the last bookmark name this invocation
+    // looked at, so the caller can pass it as `--start-after` to page into
+    // the next chunk.
+    if let Some(last) = to_process.last() {
+        info!(
+            ctx.logger(),
+            "last bookmark processed: {} (pass as --{} to resume after it)",
+            last.target_bookmark(),
+            START_AFTER_ARG,
+        );
+    }
+
     Ok(())
 }
 
@@ -1401,6 +2451,24 @@ pub fn build_subcommand<'a, 'b>() -> App<'a, 'b> {
             Arg::with_name(LARGE_REPO_HASH_ARG)
                 .required(true)
                 .help("bonsai changeset hash from large repo to verify"),
+        )
+        .arg(
+            Arg::with_name(TO_LARGE_REPO_HASH_ARG)
+                .long(TO_LARGE_REPO_HASH_ARG)
+                .required(false)
+                .takes_value(true)
+                .help(
+                    "verify every commit in the range from the positional hash (exclusive) to \
+                    this large-repo hash (inclusive), instead of a single commit",
+                ),
+        )
+        .arg(
+            Arg::with_name(LIMIT_ARG)
+                .long(LIMIT_ARG)
+                .required(false)
+                .requires(TO_LARGE_REPO_HASH_ARG)
+                .takes_value(true)
+                .help("how many commits to verify concurrently when using --to-hash"),
         );
 
     let verify_bookmarks_subcommand = SubCommand::with_name(VERIFY_BOOKMARKS_SUBCOMMAND).about(
@@ -1425,6 +2493,32 @@ pub fn build_subcommand<'a, 'b>() -> App<'a, 'b> {
             .requires(UPDATE_LARGE_REPO_BOOKMARKS)
             .takes_value(false)
             .help("don't do actual bookmark updates, only print what would be done (deriving data is real!)"),
+    ).arg(
+        Arg::with_name(RESET_CHECKPOINT_ARG)
+            .long(RESET_CHECKPOINT_ARG)
+            .required(false)
+            .requires(UPDATE_LARGE_REPO_BOOKMARKS)
+            .takes_value(false)
+            .help("ignore and reset the persisted checkpoint, reconciling the whole diff from scratch"),
+    ).arg(
+        Arg::with_name(CONCURRENCY_ARG)
+            .long(CONCURRENCY_ARG)
+            .required(false)
+            .requires(UPDATE_LARGE_REPO_BOOKMARKS)
+            .takes_value(true)
+            .help("how many bookmarks to derive data for and commit concurrently (default: 10)"),
+    ).arg(
+        Arg::with_name(START_AFTER_ARG)
+            .long(START_AFTER_ARG)
+            .required(false)
+            .requires(UPDATE_LARGE_REPO_BOOKMARKS)
+            .takes_value(true)
+            .help(
+                "page through the diff by bookmark name: skip every bookmark sorting at or \
+                before this name, instead of resuming from the persisted checkpoint. The last \
+                bookmark processed is printed on completion so it can be passed here on the \
+                next invocation",
+            ),
     );
 
     let commit_sync_config_subcommand = {
@@ -1459,8 +2553,10 @@ pub fn build_subcommand<'a, 'b>() -> App<'a, 'b> {
     let change_mapping_version = SubCommand::with_name(CHANGE_MAPPING_VERSION_SUBCOMMAND)
         .about(
             "a command to change mapping version for a given bookmark. \
-        Note that this command doesn't check that the working copies of source and target repo \
-        are equivalent according to the new mapping. This needs to ensured before calling this command",
+        Before doing so, it checks that the working copies of source and target repo are \
+        equivalent according to the outgoing mapping version; pass --skip-preflight to bypass \
+        this check. Note that this command doesn't check that the working copies are equivalent \
+        according to the new mapping. This needs to be ensured before calling this command",
         )
         .arg(
             Arg::with_name(AUTHOR_ARG)
@@ -1511,6 +2607,16 @@ pub fn build_subcommand<'a, 'b>() -> App<'a, 'b> {
                 .required(false)
                 .takes_value(true)
                 .help("Path in the repo where new mapping version will be dumped.")
+        )
+        .arg(
+            Arg::with_name(SKIP_PREFLIGHT_ARG)
+                .long(SKIP_PREFLIGHT_ARG)
+                .required(false)
+                .takes_value(false)
+                .help(
+                    "skip the preflight check that the source and target working copies are \
+                    equivalent under the outgoing mapping version",
+                ),
         );
 
     let pushredirection_subcommand = SubCommand::with_name(PUSHREDIRECTION_SUBCOMMAND)
@@ -1523,23 +2629,34 @@ pub fn build_subcommand<'a, 'b>() -> App<'a, 'b> {
         .arg(
             Arg::with_name(SOURCE_HASH_ARG)
                 .long(SOURCE_HASH_ARG)
-                .required(true)
+                .required_unless(FROM_FILE_ARG)
                 .takes_value(true)
                 .help("hash in the source repo"),
         )
         .arg(
             Arg::with_name(TARGET_HASH_ARG)
                 .long(TARGET_HASH_ARG)
-                .required(true)
+                .required_unless(FROM_FILE_ARG)
                 .takes_value(true)
                 .help("hash in the target repo"),
         )
         .arg(
             Arg::with_name(ARG_VERSION_NAME)
                 .long(ARG_VERSION_NAME)
-                .required(true)
+                .required_unless(FROM_FILE_ARG)
                 .takes_value(true)
                 .help("mapping version to write to db"),
+        )
+        .arg(
+            Arg::with_name(FROM_FILE_ARG)
+                .long(FROM_FILE_ARG)
+                .required(false)
+                .takes_value(true)
+                .conflicts_with_all(&[SOURCE_HASH_ARG, TARGET_HASH_ARG, ARG_VERSION_NAME])
+                .help(
+                    "bulk-insert mapping entries from a TSV/CSV file of \
+                    source_hash, target_hash, mapping_version rows",
+                ),
         );
 
     let equivalent_wc_subcommand = SubCommand::with_name(EQUIVALENT_WORKING_COPY_SUBCOMMAND)
@@ -1547,23 +2664,36 @@ pub fn build_subcommand<'a, 'b>() -> App<'a, 'b> {
         .arg(
             Arg::with_name(SOURCE_HASH_ARG)
                 .long(SOURCE_HASH_ARG)
-                .required(true)
+                .required_unless(FROM_FILE_ARG)
                 .takes_value(true)
                 .help("hash in the source repo"),
         )
         .arg(
             Arg::with_name(TARGET_HASH_ARG)
                 .long(TARGET_HASH_ARG)
-                .required(true)
+                .required_unless(FROM_FILE_ARG)
                 .takes_value(true)
                 .help("hash in the target repo"),
         )
         .arg(
             Arg::with_name(ARG_VERSION_NAME)
                 .long(ARG_VERSION_NAME)
-                .required(true)
+                .required_unless(FROM_FILE_ARG)
                 .takes_value(true)
                 .help("mapping version to write to db"),
+        )
+        .arg(
+            Arg::with_name(FROM_FILE_ARG)
+                .long(FROM_FILE_ARG)
+                .required(false)
+                .takes_value(true)
+                .conflicts_with_all(&[SOURCE_HASH_ARG, TARGET_HASH_ARG, ARG_VERSION_NAME])
+                .help(
+                    "bulk-insert equivalent working copy entries from a TSV/CSV file. Each \
+                    row is either source_hash, target_hash, mapping_version (3 columns), or \
+                    large_repo_hash[, mapping_version] (1 or 2 columns) for a large repo \
+                    commit with no equivalent working copy in the small repo",
+                ),
         );
 
     let not_sync_candidate_subcommand = SubCommand::with_name(NOT_SYNC_CANDIDATE_SUBCOMMAND)
@@ -1571,7 +2701,7 @@ pub fn build_subcommand<'a, 'b>() -> App<'a, 'b> {
         .arg(
             Arg::with_name(LARGE_REPO_HASH_ARG)
                 .long(LARGE_REPO_HASH_ARG)
-                .required(true)
+                .required_unless(FROM_FILE_ARG)
                 .takes_value(true)
                 .help("hash in the source repo"),
         )
@@ -1581,6 +2711,17 @@ pub fn build_subcommand<'a, 'b>() -> App<'a, 'b> {
                 .required(false)
                 .takes_value(true)
                 .help("optional mapping version to write to db"),
+        )
+        .arg(
+            Arg::with_name(FROM_FILE_ARG)
+                .long(FROM_FILE_ARG)
+                .required(false)
+                .takes_value(true)
+                .conflicts_with_all(&[LARGE_REPO_HASH_ARG])
+                .help(
+                    "bulk-insert not-sync-candidate entries from a file of \
+                    large_repo_hash[, mapping_version] rows",
+                ),
         );
 
     let insert_subcommand = SubCommand::with_name(INSERT_SUBCOMMAND)
@@ -1590,6 +2731,15 @@ pub fn build_subcommand<'a, 'b>() -> App<'a, 'b> {
         .subcommand(not_sync_candidate_subcommand);
 
     SubCommand::with_name(CROSSREPO)
+        .arg(
+            Arg::with_name(OUTPUT_ARG)
+                .long(OUTPUT_ARG)
+                .required(false)
+                .takes_value(true)
+                .possible_values(&["text", "json"])
+                .default_value("text")
+                .help("output format for commands that support it"),
+        )
         .subcommand(map_subcommand)
         .subcommand(verify_wc_subcommand)
         .subcommand(verify_bookmarks_subcommand)
@@ -1703,7 +2853,6 @@ mod test {
     use ascii::AsciiString;
     use bookmarks::BookmarkKey;
     use cacheblob::InProcessLease;
-    use commit_graph::CommitGraphRef;
     use cross_repo_sync::find_bookmark_diff;
     use fixtures::set_bookmark;
     use fixtures::Linear;
@@ -1723,6 +2872,42 @@ mod test {
 
     use super::*;
 
+    #[test]
+    fn test_verify_bookmarks_checkpoint_key_name() {
+        let small_repo_id = RepositoryId::new(0);
+        let large_repo_id = RepositoryId::new(1);
+        let key = format_verify_bookmarks_checkpoint_key(small_repo_id, large_repo_id);
+        assert_eq!(key, "xrepo_sync.verify_bookmarks_checkpoint.0.1");
+
+        // Distinct repo pairs must not collide on the same checkpoint key.
+        assert_ne!(
+            key,
+            format_verify_bookmarks_checkpoint_key(large_repo_id, small_repo_id)
+        );
+    }
+
+    #[test]
+    fn test_output_format_parse() -> Result<(), Error> {
+        let app = App::new("test").arg(
+            Arg::with_name(OUTPUT_ARG)
+                .long(OUTPUT_ARG)
+                .takes_value(true),
+        );
+
+        let matches = app.clone().get_matches_from(vec!["test"]);
+        assert_eq!(OutputFormat::parse(&matches)?, OutputFormat::Text);
+
+        let matches = app
+            .clone()
+            .get_matches_from(vec!["test", "--output", "json"]);
+        assert_eq!(OutputFormat::parse(&matches)?, OutputFormat::Json);
+
+        let matches = app.get_matches_from(vec!["test", "--output", "bogus"]);
+        assert!(OutputFormat::parse(&matches).is_err());
+
+        Ok(())
+    }
+
     #[fbinit::test]
     fn test_bookmark_diff(fb: FacebookInit) -> Result<(), Error> {
         let runtime = tokio::runtime::Runtime::new()?;
@@ -1804,6 +2989,9 @@ mod test {
                 &common_config,
                 UpdateLargeRepoBookmarksMode::Real,
                 None,
+                false,
+                DEFAULT_UPDATE_LARGE_REPO_BOOKMARKS_CONCURRENCY,
+                None,
             )
             .await?;
 
@@ -1833,6 +3021,9 @@ mod test {
                 &common_config,
                 UpdateLargeRepoBookmarksMode::Real,
                 None,
+                false,
+                DEFAULT_UPDATE_LARGE_REPO_BOOKMARKS_CONCURRENCY,
+                None,
             )
             .await?;
             let actual_diff = find_bookmark_diff(ctx.clone(), &syncers.large_to_small).await?;
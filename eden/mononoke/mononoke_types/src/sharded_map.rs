@@ -7,11 +7,21 @@
 
 #![allow(dead_code)]
 
+use std::collections::BTreeMap;
+use std::collections::BTreeSet;
+
 use anyhow::{anyhow, bail, Context, Error, Result};
+use async_recursion::async_recursion;
 use blobstore::Blobstore;
+use blobstore::BlobstoreBytes;
 use bytes::Bytes;
 use context::CoreContext;
 use fbthrift::compact_protocol;
+use futures::future::try_join;
+use futures::stream::{self, BoxStream};
+use futures::StreamExt;
+use futures::TryFutureExt;
+use futures::TryStreamExt;
 use smallvec::SmallVec;
 use sorted_vector_map::SortedVectorMap;
 
@@ -32,6 +42,31 @@ pub trait MapValue =
 
 type SmallBinary = SmallVec<[u8; 24]>;
 
+/// Default bound on the number of `MapChild::Id` children fetched from the
+/// blobstore concurrently while streaming entries out of a map.
+const DEFAULT_FETCH_CONCURRENCY: usize = 100;
+
+/// Maximum number of entries a `Terminal` node may hold before it is split
+/// into an `Intermediate` node keyed on the next diverging byte.
+const TERMINAL_SPLIT_THRESHOLD: usize = 2000;
+
+/// An `Intermediate` node whose total entry count drops to this size or
+/// below is collapsed back into a single `Terminal`. Kept well under
+/// `TERMINAL_SPLIT_THRESHOLD` so a map sitting near the boundary doesn't
+/// thrash between the two shapes as entries are added and removed.
+const MERGE_THRESHOLD: usize = TERMINAL_SPLIT_THRESHOLD / 4;
+
+/// At or below this serialized size, a child is kept `Inlined` in its parent
+/// rather than being spilled out to its own content-addressed blob.
+const INLINE_SIZE_THRESHOLD: usize = 2 * 1024;
+
+/// Returns whether a subtree whose keys all start with `accumulated` can
+/// possibly contain a key starting with `prefix`.
+fn is_prefix_compatible(accumulated: &[u8], prefix: &[u8]) -> bool {
+    let len = std::cmp::min(accumulated.len(), prefix.len());
+    accumulated[..len] == prefix[..len]
+}
+
 #[derive(Debug, Clone, Eq, PartialEq, Hash)]
 pub enum ShardedMapNode<Value: MapValue> {
     Intermediate {
@@ -163,6 +198,592 @@ impl<Value: MapValue> ShardedMapNode<Value> {
             .with_context(|| ErrorKind::BlobDeserializeError("ShardedMapNode".into()))?;
         Self::from_thrift(thrift_tc)
     }
+
+    /// Looks up `key` by navigating the trie, loading any child nodes that are
+    /// stored out-of-line by their `ShardedMapNodeId` along the way.
+    #[async_recursion]
+    pub async fn lookup(
+        &self,
+        ctx: &CoreContext,
+        blobstore: &impl Blobstore,
+        key: &[u8],
+    ) -> Result<Option<Value>> {
+        match self {
+            Self::Intermediate {
+                prefix,
+                value,
+                children,
+                ..
+            } => {
+                let key = match key.strip_prefix(prefix.as_slice()) {
+                    Some(key) => key,
+                    None => return Ok(None),
+                };
+                match key.split_first() {
+                    None => Ok(value.clone()),
+                    Some((edge, rest)) => match children.get(edge) {
+                        None => Ok(None),
+                        Some(child) => {
+                            let child = child.clone().load(ctx, blobstore).await?;
+                            child.lookup(ctx, blobstore, rest).await
+                        }
+                    },
+                }
+            }
+            Self::Terminal { values } => Ok(values.get(key).cloned()),
+        }
+    }
+
+    /// Streams every `(full_key, value)` pair in the map in sorted order,
+    /// fetching `MapChild::Id` children with bounded concurrency so a wide
+    /// tree doesn't issue unbounded blobstore gets or hold itself in memory.
+    pub fn into_entries<'a>(
+        self,
+        ctx: &'a CoreContext,
+        blobstore: &'a impl Blobstore,
+    ) -> BoxStream<'a, Result<(Vec<u8>, Value)>> {
+        self.into_entries_with_concurrency(ctx, blobstore, DEFAULT_FETCH_CONCURRENCY)
+    }
+
+    /// Like `into_entries`, but lets the caller override the `MapChild::Id`
+    /// fetch concurrency instead of using `DEFAULT_FETCH_CONCURRENCY`, for
+    /// callers that need to bound a particularly wide tree more tightly (or
+    /// can afford to widen it).
+    pub fn into_entries_with_concurrency<'a>(
+        self,
+        ctx: &'a CoreContext,
+        blobstore: &'a impl Blobstore,
+        fetch_concurrency: usize,
+    ) -> BoxStream<'a, Result<(Vec<u8>, Value)>> {
+        self.into_entries_impl(ctx, blobstore, Vec::new(), None, fetch_concurrency)
+    }
+
+    /// Like `into_entries`, but prunes subtrees whose accumulated path
+    /// cannot match `prefix`, so callers can stream a key range without
+    /// loading unrelated shards.
+    pub fn prefix_iter<'a>(
+        self,
+        ctx: &'a CoreContext,
+        blobstore: &'a impl Blobstore,
+        prefix: &'a [u8],
+    ) -> BoxStream<'a, Result<(Vec<u8>, Value)>> {
+        self.prefix_iter_with_concurrency(ctx, blobstore, prefix, DEFAULT_FETCH_CONCURRENCY)
+    }
+
+    /// Like `prefix_iter`, but lets the caller override the `MapChild::Id`
+    /// fetch concurrency instead of using `DEFAULT_FETCH_CONCURRENCY`.
+    pub fn prefix_iter_with_concurrency<'a>(
+        self,
+        ctx: &'a CoreContext,
+        blobstore: &'a impl Blobstore,
+        prefix: &'a [u8],
+        fetch_concurrency: usize,
+    ) -> BoxStream<'a, Result<(Vec<u8>, Value)>> {
+        self.into_entries_impl(ctx, blobstore, Vec::new(), Some(prefix), fetch_concurrency)
+    }
+
+    fn into_entries_impl<'a>(
+        self,
+        ctx: &'a CoreContext,
+        blobstore: &'a impl Blobstore,
+        accumulated: Vec<u8>,
+        prefix: Option<&'a [u8]>,
+        fetch_concurrency: usize,
+    ) -> BoxStream<'a, Result<(Vec<u8>, Value)>> {
+        match self {
+            Self::Terminal { values } => stream::iter(values.into_iter().filter_map(move |(suffix, value)| {
+                let mut full_key = accumulated.clone();
+                full_key.extend_from_slice(&suffix);
+                match prefix {
+                    Some(prefix) if !full_key.starts_with(prefix) => None,
+                    _ => Some(Ok((full_key, value))),
+                }
+            }))
+            .boxed(),
+            Self::Intermediate {
+                prefix: node_prefix,
+                value,
+                children,
+                ..
+            } => {
+                let mut own_key = accumulated;
+                own_key.extend_from_slice(&node_prefix);
+
+                let own_entry = value.and_then(|value| match prefix {
+                    Some(prefix) if !own_key.starts_with(prefix) => None,
+                    _ => Some(Ok((own_key.clone(), value))),
+                });
+
+                let children_stream = stream::iter(children.into_iter().filter_map(move |(edge, child)| {
+                    let mut child_accumulated = own_key.clone();
+                    child_accumulated.push(edge);
+                    if let Some(prefix) = prefix {
+                        if !is_prefix_compatible(&child_accumulated, prefix) {
+                            return None;
+                        }
+                    }
+                    Some((child, child_accumulated))
+                }))
+                .map(move |(child, child_accumulated)| async move {
+                    let child = child.load(ctx, blobstore).await?;
+                    Ok(child.into_entries_impl(ctx, blobstore, child_accumulated, prefix, fetch_concurrency))
+                })
+                .buffered(fetch_concurrency)
+                .map(|result| result.unwrap_or_else(|e: Error| stream::once(async move { Err(e) }).boxed()))
+                .flatten();
+
+                stream::iter(own_entry).chain(children_stream).boxed()
+            }
+        }
+    }
+
+    /// Diffs `self` against `other`, streaming an entry for every key that
+    /// was added, removed or changed between the two maps.
+    ///
+    /// Whenever two children at the same edge point at the same
+    /// `ShardedMapNodeId`, the whole subtree is known to be identical (the
+    /// id is content-addressed) and is skipped without being loaded, which
+    /// makes this an O(changed-subtrees) diff rather than a full scan of
+    /// both maps.
+    pub fn diff<'a>(
+        self,
+        ctx: &'a CoreContext,
+        blobstore: &'a impl Blobstore,
+        other: ShardedMapNode<Value>,
+    ) -> BoxStream<'a, Result<DiffEntry<Value>>> {
+        diff_nodes(ctx, blobstore, Vec::new(), self, other)
+    }
+
+    /// Inserts `value` at `key`, re-sharding the map as needed. Touched
+    /// children too large to stay inlined are written to the blobstore; the
+    /// returned root is not stored (see `extend`).
+    pub async fn insert(
+        self,
+        ctx: &CoreContext,
+        blobstore: &impl Blobstore,
+        key: &[u8],
+        value: Value,
+    ) -> Result<Self> {
+        self.extend(ctx, blobstore, vec![(key.to_vec(), Some(value))])
+            .await
+    }
+
+    /// Removes `key` if present, re-sharding the map as needed. Touched
+    /// children too large to stay inlined are written to the blobstore; the
+    /// returned root is not stored (see `extend`).
+    pub async fn remove(self, ctx: &CoreContext, blobstore: &impl Blobstore, key: &[u8]) -> Result<Self> {
+        self.extend(ctx, blobstore, vec![(key.to_vec(), None)]).await
+    }
+
+    /// Applies a batch of inserts (`Some(value)`) and deletes (`None`)
+    /// to this map in one pass, re-sharding `Terminal`/`Intermediate`
+    /// nodes as needed and only re-serializing the nodes actually touched
+    /// by the batch, then returns the new root. The caller is responsible
+    /// for storing the returned root itself (e.g. via `BlobstoreValue`),
+    /// exactly as it would for a freshly-built map.
+    pub async fn extend(
+        self,
+        ctx: &CoreContext,
+        blobstore: &impl Blobstore,
+        updates: Vec<(Vec<u8>, Option<Value>)>,
+    ) -> Result<Self> {
+        let updates = updates
+            .into_iter()
+            .map(|(key, value)| (SmallBinary::from_slice(&key), value))
+            .collect();
+        apply_updates(ctx, blobstore, self, updates).await
+    }
+}
+
+fn common_prefix_len(a: &[u8], b: &[u8]) -> usize {
+    a.iter().zip(b.iter()).take_while(|(x, y)| x == y).count()
+}
+
+fn longest_common_prefix<'a>(mut keys: impl Iterator<Item = &'a [u8]>) -> SmallBinary {
+    let first = match keys.next() {
+        Some(first) => first,
+        None => return SmallBinary::new(),
+    };
+    let len = keys.fold(first.len(), |len, key| common_prefix_len(&first[..len], key));
+    SmallBinary::from_slice(&first[..len])
+}
+
+/// Stores `node` into the blobstore and returns a reference to it, unless
+/// it is small enough to stay inlined in its parent.
+async fn store_child<Value: MapValue>(
+    ctx: &CoreContext,
+    blobstore: &impl Blobstore,
+    node: ShardedMapNode<Value>,
+) -> Result<MapChild<Value>> {
+    let blob = node.clone().into_blob();
+    if blob.data().len() <= INLINE_SIZE_THRESHOLD {
+        return Ok(MapChild::Inlined(node));
+    }
+    let id = *blob.id();
+    blobstore
+        .put(
+            ctx,
+            id.blobstore_key(),
+            BlobstoreBytes::from_bytes(blob.data().clone()),
+        )
+        .await?;
+    Ok(MapChild::Id(id))
+}
+
+#[async_recursion]
+async fn apply_updates<Value: MapValue>(
+    ctx: &CoreContext,
+    blobstore: &(impl Blobstore + 'async_recursion),
+    node: ShardedMapNode<Value>,
+    updates: Vec<(SmallBinary, Option<Value>)>,
+) -> Result<ShardedMapNode<Value>> {
+    if updates.is_empty() {
+        return Ok(node);
+    }
+
+    match node {
+        ShardedMapNode::Terminal { mut values } => {
+            for (key, value) in updates {
+                match value {
+                    Some(value) => {
+                        values.insert(key, value);
+                    }
+                    None => {
+                        values.remove(&key);
+                    }
+                }
+            }
+            reshard_terminal(ctx, blobstore, values).await
+        }
+        ShardedMapNode::Intermediate {
+            prefix,
+            value,
+            value_count,
+            mut children,
+        } => {
+            let common = updates.iter().fold(prefix.len(), |common, (key, _)| {
+                common.min(common_prefix_len(&prefix, key))
+            });
+
+            if common < prefix.len() {
+                // At least one update diverges from this node's prefix
+                // before reaching its end: split the node at the point of
+                // divergence so the new key(s) can be accommodated.
+                let (shared, rest) = prefix.split_at(common);
+                let (&edge, demoted_prefix) = rest.split_first().expect("common < prefix.len()");
+
+                let demoted = ShardedMapNode::Intermediate {
+                    prefix: SmallBinary::from_slice(demoted_prefix),
+                    value,
+                    value_count,
+                    children,
+                };
+                let split_node = ShardedMapNode::Intermediate {
+                    prefix: SmallBinary::from_slice(shared),
+                    value: None,
+                    value_count,
+                    children: std::iter::once((edge, MapChild::Inlined(demoted))).collect(),
+                };
+                return apply_updates(ctx, blobstore, split_node, updates).await;
+            }
+
+            let mut own_update = None;
+            let mut by_edge: BTreeMap<u8, Vec<(SmallBinary, Option<Value>)>> = BTreeMap::new();
+            for (key, update) in updates {
+                let rest = &key[prefix.len()..];
+                match rest.split_first() {
+                    None => own_update = Some(update),
+                    Some((&edge, suffix)) => by_edge
+                        .entry(edge)
+                        .or_default()
+                        .push((SmallBinary::from_slice(suffix), update)),
+                }
+            }
+
+            let mut value_count = value_count;
+            if let Some(update) = own_update {
+                match (&value, &update) {
+                    (None, Some(_)) => value_count += 1,
+                    (Some(_), None) => value_count -= 1,
+                    _ => {}
+                }
+            }
+            let value = own_update.unwrap_or(value);
+
+            for (edge, edge_updates) in by_edge {
+                let existing = children.remove(&edge);
+                let (old_size, child) = match existing {
+                    Some(child) => {
+                        let child = child.load(ctx, blobstore).await?;
+                        (child.size(), child)
+                    }
+                    None => (
+                        0,
+                        ShardedMapNode::Terminal {
+                            values: Default::default(),
+                        },
+                    ),
+                };
+
+                let new_child = apply_updates(ctx, blobstore, child, edge_updates).await?;
+                let new_size = new_child.size();
+                value_count = ((value_count as i64) + (new_size as i64) - (old_size as i64)) as usize;
+
+                if !new_child.is_empty() {
+                    children.insert(edge, store_child(ctx, blobstore, new_child).await?);
+                }
+            }
+
+            let node = ShardedMapNode::Intermediate {
+                prefix,
+                value,
+                value_count,
+                children,
+            };
+            maybe_merge_to_terminal(ctx, blobstore, node).await
+        }
+    }
+}
+
+#[async_recursion]
+async fn reshard_terminal<Value: MapValue>(
+    ctx: &CoreContext,
+    blobstore: &(impl Blobstore + 'async_recursion),
+    values: SortedVectorMap<SmallBinary, Value>,
+) -> Result<ShardedMapNode<Value>> {
+    if values.len() <= TERMINAL_SPLIT_THRESHOLD {
+        return Ok(ShardedMapNode::Terminal { values });
+    }
+
+    let common = longest_common_prefix(values.keys().map(SmallVec::as_slice));
+
+    let mut own_value = None;
+    let mut grouped: BTreeMap<u8, SortedVectorMap<SmallBinary, Value>> = BTreeMap::new();
+    for (key, value) in values {
+        let rest = &key[common.len()..];
+        match rest.split_first() {
+            None => own_value = Some(value),
+            Some((&edge, suffix)) => {
+                grouped
+                    .entry(edge)
+                    .or_default()
+                    .insert(SmallBinary::from_slice(suffix), value);
+            }
+        }
+    }
+
+    let mut value_count = own_value.is_some() as usize;
+    let mut children = SortedVectorMap::new();
+    for (edge, group) in grouped {
+        value_count += group.len();
+        let child = reshard_terminal(ctx, blobstore, group).await?;
+        children.insert(edge, store_child(ctx, blobstore, child).await?);
+    }
+
+    Ok(ShardedMapNode::Intermediate {
+        prefix: common,
+        value: own_value,
+        value_count,
+        children,
+    })
+}
+
+/// Collapses an `Intermediate` node back into a `Terminal` once its entry
+/// count has dropped to `MERGE_THRESHOLD` or below.
+async fn maybe_merge_to_terminal<Value: MapValue>(
+    ctx: &CoreContext,
+    blobstore: &impl Blobstore,
+    node: ShardedMapNode<Value>,
+) -> Result<ShardedMapNode<Value>> {
+    match &node {
+        ShardedMapNode::Terminal { .. } => Ok(node),
+        ShardedMapNode::Intermediate { value_count, .. } if *value_count > MERGE_THRESHOLD => {
+            Ok(node)
+        }
+        ShardedMapNode::Intermediate { .. } => {
+            let values = node
+                .into_entries(ctx, blobstore)
+                .map_ok(|(key, value)| (SmallBinary::from_slice(&key), value))
+                .try_collect()
+                .await?;
+            Ok(ShardedMapNode::Terminal { values })
+        }
+    }
+}
+
+/// An entry that differs between two `ShardedMapNode`s, keyed by the full,
+/// reconstructed map key.
+#[derive(Debug, Clone)]
+pub enum DiffEntry<Value: MapValue> {
+    Added(Vec<u8>, Value),
+    Removed(Vec<u8>, Value),
+    Changed(Vec<u8>, Value, Value),
+}
+
+fn values_equal<Value: MapValue>(left: &Value, right: &Value) -> bool {
+    let left: Bytes = left.clone().into();
+    let right: Bytes = right.clone().into();
+    left == right
+}
+
+fn diff_nodes<'a, Value: MapValue>(
+    ctx: &'a CoreContext,
+    blobstore: &'a impl Blobstore,
+    accumulated: Vec<u8>,
+    left: ShardedMapNode<Value>,
+    right: ShardedMapNode<Value>,
+) -> BoxStream<'a, Result<DiffEntry<Value>>> {
+    match (left, right) {
+        (
+            ShardedMapNode::Intermediate {
+                prefix: left_prefix,
+                value: left_value,
+                children: left_children,
+                ..
+            },
+            ShardedMapNode::Intermediate {
+                prefix: right_prefix,
+                value: right_value,
+                children: right_children,
+                ..
+            },
+        ) if left_prefix == right_prefix => {
+            let mut own_key = accumulated;
+            own_key.extend_from_slice(&left_prefix);
+
+            let value_diff = diff_values(own_key.clone(), left_value, right_value);
+            let children_diff = diff_children(ctx, blobstore, own_key, left_children, right_children);
+
+            stream::iter(value_diff).chain(children_diff).boxed()
+        }
+        // Structural mismatch: different prefixes, or a Terminal lines up
+        // against an Intermediate. This can't use the id short-circuit, so
+        // fall back to comparing the fully expanded entries under this path.
+        (left, right) => diff_by_expansion(ctx, blobstore, accumulated, left, right),
+    }
+}
+
+fn diff_values<Value: MapValue>(
+    key: Vec<u8>,
+    left: Option<Value>,
+    right: Option<Value>,
+) -> Option<Result<DiffEntry<Value>>> {
+    match (left, right) {
+        (None, None) => None,
+        (Some(left), None) => Some(Ok(DiffEntry::Removed(key, left))),
+        (None, Some(right)) => Some(Ok(DiffEntry::Added(key, right))),
+        (Some(left), Some(right)) if values_equal(&left, &right) => None,
+        (Some(left), Some(right)) => Some(Ok(DiffEntry::Changed(key, left, right))),
+    }
+}
+
+enum ChildDiffWork<Value: MapValue> {
+    Both(u8, MapChild<Value>, MapChild<Value>),
+    LeftOnly(u8, MapChild<Value>),
+    RightOnly(u8, MapChild<Value>),
+}
+
+fn diff_children<'a, Value: MapValue>(
+    ctx: &'a CoreContext,
+    blobstore: &'a impl Blobstore,
+    accumulated: Vec<u8>,
+    left: SortedVectorMap<u8, MapChild<Value>>,
+    right: SortedVectorMap<u8, MapChild<Value>>,
+) -> BoxStream<'a, Result<DiffEntry<Value>>> {
+    let mut left: BTreeMap<_, _> = left.into_iter().collect();
+    let mut right: BTreeMap<_, _> = right.into_iter().collect();
+    let edges: BTreeSet<u8> = left.keys().chain(right.keys()).copied().collect();
+
+    let work: Vec<_> = edges
+        .into_iter()
+        .filter_map(|edge| match (left.remove(&edge), right.remove(&edge)) {
+            (Some(MapChild::Id(left_id)), Some(MapChild::Id(right_id))) if left_id == right_id => {
+                // Content-addressed: identical id means identical subtree.
+                None
+            }
+            (Some(left), Some(right)) => Some(ChildDiffWork::Both(edge, left, right)),
+            (Some(left), None) => Some(ChildDiffWork::LeftOnly(edge, left)),
+            (None, Some(right)) => Some(ChildDiffWork::RightOnly(edge, right)),
+            (None, None) => None,
+        })
+        .collect();
+
+    stream::iter(work)
+        .map(move |work| {
+            let mut child_path = accumulated.clone();
+            async move {
+                Result::<_, Error>::Ok(match work {
+                    ChildDiffWork::Both(edge, left, right) => {
+                        child_path.push(edge);
+                        let (left, right) =
+                            try_join(left.load(ctx, blobstore), right.load(ctx, blobstore)).await?;
+                        diff_nodes(ctx, blobstore, child_path, left, right)
+                    }
+                    ChildDiffWork::LeftOnly(edge, left) => {
+                        child_path.push(edge);
+                        let left = left.load(ctx, blobstore).await?;
+                        left.into_entries_impl(ctx, blobstore, child_path, None, DEFAULT_FETCH_CONCURRENCY)
+                            .map_ok(|(key, value)| DiffEntry::Removed(key, value))
+                            .boxed()
+                    }
+                    ChildDiffWork::RightOnly(edge, right) => {
+                        child_path.push(edge);
+                        let right = right.load(ctx, blobstore).await?;
+                        right
+                            .into_entries_impl(ctx, blobstore, child_path, None, DEFAULT_FETCH_CONCURRENCY)
+                            .map_ok(|(key, value)| DiffEntry::Added(key, value))
+                            .boxed()
+                    }
+                })
+            }
+        })
+        .buffered(DEFAULT_FETCH_CONCURRENCY)
+        .map(|result| result.unwrap_or_else(|e| stream::once(async move { Err(e) }).boxed()))
+        .flatten()
+        .boxed()
+}
+
+fn diff_by_expansion<'a, Value: MapValue>(
+    ctx: &'a CoreContext,
+    blobstore: &'a impl Blobstore,
+    accumulated: Vec<u8>,
+    left: ShardedMapNode<Value>,
+    right: ShardedMapNode<Value>,
+) -> BoxStream<'a, Result<DiffEntry<Value>>> {
+    async move {
+        let left_entries: BTreeMap<Vec<u8>, Value> = left
+            .into_entries_impl(ctx, blobstore, accumulated.clone(), None, DEFAULT_FETCH_CONCURRENCY)
+            .try_collect()
+            .await?;
+        let right_entries: BTreeMap<Vec<u8>, Value> = right
+            .into_entries_impl(ctx, blobstore, accumulated, None, DEFAULT_FETCH_CONCURRENCY)
+            .try_collect()
+            .await?;
+
+        let mut diff = Vec::new();
+        for (key, left_value) in left_entries.iter() {
+            match right_entries.get(key) {
+                None => diff.push(DiffEntry::Removed(key.clone(), left_value.clone())),
+                Some(right_value) if !values_equal(left_value, right_value) => {
+                    diff.push(DiffEntry::Changed(
+                        key.clone(),
+                        left_value.clone(),
+                        right_value.clone(),
+                    ));
+                }
+                Some(_) => {}
+            }
+        }
+        for (key, right_value) in right_entries {
+            if !left_entries.contains_key(&key) {
+                diff.push(DiffEntry::Added(key, right_value));
+            }
+        }
+
+        Result::<_, Error>::Ok(stream::iter(diff.into_iter().map(Ok)))
+    }
+    .try_flatten_stream()
+    .boxed()
 }
 
 impl<Value: MapValue> BlobstoreValue for ShardedMapNode<Value> {
@@ -182,10 +803,178 @@ impl<Value: MapValue> BlobstoreValue for ShardedMapNode<Value> {
     }
 }
 
+/// Human-readable JSON (de)serialization of `ShardedMapNode`, independent of
+/// the thrift compact wire format used by `from_bytes`/`into_blob`. This
+/// exists for admin tooling (e.g. `newadmin`) that wants to dump a map node
+/// for inspection, edit it, and reload it - not for the blobstore storage
+/// path, which always goes through thrift.
+///
+/// This checkout has no `Cargo.toml` for `mononoke_types` (or anywhere else
+/// in the tree), so the `json` feature below can't actually be registered or
+/// built here; this module is written as it would need to land alongside a
+/// manifest change adding, under `mononoke_types/Cargo.toml`:
+///   [dependencies]
+///   serde = { workspace = true, optional = true }
+///   hex = { workspace = true, optional = true }
+///   [dev-dependencies]
+///   serde_json = { workspace = true }
+///   [features]
+///   json = ["dep:serde", "dep:hex"]
+/// and `test_json_round_trip` below run via `cargo test -p mononoke_types
+/// --features json`.
+#[cfg(feature = "json")]
+mod json {
+    use std::collections::BTreeMap;
+    use std::str::FromStr;
+
+    use serde::de::Error as DeError;
+    use serde::Deserialize;
+    use serde::Deserializer;
+    use serde::Serialize;
+    use serde::Serializer;
+
+    use super::MapChild;
+    use super::MapValue;
+    use super::ShardedMapNode;
+    use super::SmallBinary;
+    use crate::typed_hash::ShardedMapNodeId;
+
+    #[derive(Serialize, Deserialize)]
+    #[serde(tag = "kind", rename_all = "snake_case")]
+    enum JsonNode {
+        Intermediate {
+            prefix: String,
+            value: Option<String>,
+            value_count: usize,
+            children: BTreeMap<String, JsonChild>,
+        },
+        Terminal {
+            values: BTreeMap<String, String>,
+        },
+    }
+
+    #[derive(Serialize, Deserialize)]
+    #[serde(tag = "kind", rename_all = "snake_case")]
+    enum JsonChild {
+        Inlined { node: JsonNode },
+        Id { id: String },
+    }
+
+    impl<Value: MapValue> ShardedMapNode<Value> {
+        fn to_json_node(&self) -> JsonNode {
+            match self {
+                Self::Intermediate {
+                    prefix,
+                    value,
+                    value_count,
+                    children,
+                } => JsonNode::Intermediate {
+                    prefix: hex::encode(prefix),
+                    value: value
+                        .as_ref()
+                        .map(|value| hex::encode(bytes::Bytes::from(value.clone()))),
+                    value_count: *value_count,
+                    children: children
+                        .iter()
+                        .map(|(edge, child)| (hex::encode([*edge]), child.to_json_child()))
+                        .collect(),
+                },
+                Self::Terminal { values } => JsonNode::Terminal {
+                    values: values
+                        .iter()
+                        .map(|(suffix, value)| {
+                            (
+                                hex::encode(suffix),
+                                hex::encode(bytes::Bytes::from(value.clone())),
+                            )
+                        })
+                        .collect(),
+                },
+            }
+        }
+
+        fn from_json_node(node: JsonNode) -> anyhow::Result<Self> {
+            Ok(match node {
+                JsonNode::Intermediate {
+                    prefix,
+                    value,
+                    value_count,
+                    children,
+                } => Self::Intermediate {
+                    prefix: SmallBinary::from_slice(&hex::decode(prefix)?),
+                    value: value
+                        .map(|value| Value::try_from(bytes::Bytes::from(hex::decode(value)?)))
+                        .transpose()?,
+                    value_count,
+                    children: children
+                        .into_iter()
+                        .map(|(edge, child)| {
+                            let edge = hex::decode(edge)?;
+                            let edge = *edge
+                                .first()
+                                .ok_or_else(|| anyhow::anyhow!("empty edge byte"))?;
+                            Ok((edge, MapChild::from_json_child(child)?))
+                        })
+                        .collect::<anyhow::Result<_>>()?,
+                },
+                JsonNode::Terminal { values } => Self::Terminal {
+                    values: values
+                        .into_iter()
+                        .map(|(suffix, value)| {
+                            Ok((
+                                SmallBinary::from_slice(&hex::decode(suffix)?),
+                                Value::try_from(bytes::Bytes::from(hex::decode(value)?))?,
+                            ))
+                        })
+                        .collect::<anyhow::Result<_>>()?,
+                },
+            })
+        }
+    }
+
+    impl<Value: MapValue> MapChild<Value> {
+        fn to_json_child(&self) -> JsonChild {
+            match self {
+                Self::Inlined(node) => JsonChild::Inlined {
+                    node: node.to_json_node(),
+                },
+                Self::Id(id) => JsonChild::Id {
+                    id: id.to_hex().to_string(),
+                },
+            }
+        }
+
+        fn from_json_child(child: JsonChild) -> anyhow::Result<Self> {
+            Ok(match child {
+                JsonChild::Inlined { node } => {
+                    Self::Inlined(ShardedMapNode::from_json_node(node)?)
+                }
+                JsonChild::Id { id } => Self::Id(ShardedMapNodeId::from_str(&id)?),
+            })
+        }
+    }
+
+    impl<Value: MapValue> Serialize for ShardedMapNode<Value> {
+        fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+            self.to_json_node().serialize(serializer)
+        }
+    }
+
+    impl<'de, Value: MapValue> Deserialize<'de> for ShardedMapNode<Value> {
+        fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+            let node = JsonNode::deserialize(deserializer)?;
+            Self::from_json_node(node).map_err(DeError::custom)
+        }
+    }
+}
+
 #[cfg(test)]
 mod test {
     use super::*;
     use bytes::{Buf, BufMut, BytesMut};
+    use fbinit::FacebookInit;
+    use futures::TryStreamExt;
+    use memblob::Memblob;
 
     #[derive(Debug, Clone, Copy, Eq, PartialEq)]
     struct MyType(i32);
@@ -279,4 +1068,289 @@ mod test {
         assert_eq!(map.size(), 11);
         assert_round_trip(map);
     }
+
+    #[fbinit::test]
+    async fn test_lookup(fb: FacebookInit) {
+        let ctx = CoreContext::test_mock(fb);
+        let blobstore = Memblob::default();
+        let map = example_map();
+
+        for (key, value) in [
+            ("abacab", 7),
+            ("abacaba", 8),
+            ("abacakkk", 9),
+            ("abacate", 10),
+            ("abacaxi", 11),
+            ("abalaba", 5),
+            ("abalada", 6),
+            ("omiojo", 1),
+            ("omiux", 2),
+            ("omundo", 3),
+            ("omungal", 4),
+        ] {
+            assert_eq!(
+                map.lookup(&ctx, &blobstore, key.as_bytes()).await.unwrap(),
+                Some(MyType(value)),
+            );
+        }
+
+        for key in ["", "a", "ab", "aba", "abc", "omi", "xyz"] {
+            assert_eq!(
+                map.lookup(&ctx, &blobstore, key.as_bytes()).await.unwrap(),
+                None,
+            );
+        }
+    }
+
+    #[fbinit::test]
+    async fn test_into_entries(fb: FacebookInit) {
+        let ctx = CoreContext::test_mock(fb);
+        let blobstore = Memblob::default();
+        let map = example_map();
+
+        let mut entries: Vec<_> = map
+            .clone()
+            .into_entries(&ctx, &blobstore)
+            .try_collect()
+            .await
+            .unwrap();
+        entries.sort();
+        let mut expected = vec![
+            (b"abacab".to_vec(), MyType(7)),
+            (b"abacaba".to_vec(), MyType(8)),
+            (b"abacakkk".to_vec(), MyType(9)),
+            (b"abacate".to_vec(), MyType(10)),
+            (b"abacaxi".to_vec(), MyType(11)),
+            (b"abalaba".to_vec(), MyType(5)),
+            (b"abalada".to_vec(), MyType(6)),
+            (b"omiojo".to_vec(), MyType(1)),
+            (b"omiux".to_vec(), MyType(2)),
+            (b"omundo".to_vec(), MyType(3)),
+            (b"omungal".to_vec(), MyType(4)),
+        ];
+        expected.sort();
+        assert_eq!(entries, expected);
+
+        let mut prefixed: Vec<_> = map
+            .prefix_iter(&ctx, &blobstore, b"abac")
+            .try_collect()
+            .await
+            .unwrap();
+        prefixed.sort();
+        let mut expected_prefixed = vec![
+            (b"abacab".to_vec(), MyType(7)),
+            (b"abacaba".to_vec(), MyType(8)),
+            (b"abacakkk".to_vec(), MyType(9)),
+            (b"abacate".to_vec(), MyType(10)),
+            (b"abacaxi".to_vec(), MyType(11)),
+        ];
+        expected_prefixed.sort();
+        assert_eq!(prefixed, expected_prefixed);
+    }
+
+    #[fbinit::test]
+    async fn test_into_entries_with_concurrency(fb: FacebookInit) {
+        let ctx = CoreContext::test_mock(fb);
+        let blobstore = Memblob::default();
+        let map = example_map();
+
+        let mut entries: Vec<_> = map
+            .clone()
+            .into_entries_with_concurrency(&ctx, &blobstore, 1)
+            .try_collect()
+            .await
+            .unwrap();
+        entries.sort();
+
+        let mut via_default: Vec<_> = map
+            .clone()
+            .into_entries(&ctx, &blobstore)
+            .try_collect()
+            .await
+            .unwrap();
+        via_default.sort();
+        assert_eq!(entries, via_default);
+
+        let mut prefixed: Vec<_> = map
+            .prefix_iter_with_concurrency(&ctx, &blobstore, b"abac", 1)
+            .try_collect()
+            .await
+            .unwrap();
+        prefixed.sort();
+        let mut expected_prefixed = vec![
+            (b"abacab".to_vec(), MyType(7)),
+            (b"abacaba".to_vec(), MyType(8)),
+            (b"abacakkk".to_vec(), MyType(9)),
+            (b"abacate".to_vec(), MyType(10)),
+            (b"abacaxi".to_vec(), MyType(11)),
+        ];
+        expected_prefixed.sort();
+        assert_eq!(prefixed, expected_prefixed);
+    }
+
+    #[fbinit::test]
+    async fn test_diff(fb: FacebookInit) {
+        let ctx = CoreContext::test_mock(fb);
+        let blobstore = Memblob::default();
+
+        let left = example_map();
+        // Same shape, but "abacab" changes value and "omiux" is removed
+        // while a brand new "zzz" key is added.
+        let right = intermediate(
+            "",
+            None,
+            vec![
+                (
+                    'a',
+                    intermediate(
+                        "ba",
+                        None,
+                        vec![
+                            (
+                                'c',
+                                terminal(vec![
+                                    ("ab", 70),
+                                    ("aba", 8),
+                                    ("akkk", 9),
+                                    ("ate", 10),
+                                    ("axi", 11),
+                                ]),
+                            ),
+                            ('l', terminal(vec![("aba", 5), ("ada", 6)])),
+                        ],
+                    ),
+                ),
+                (
+                    'o',
+                    terminal(vec![("miojo", 1), ("mundo", 3), ("mungal", 4)]),
+                ),
+                ('z', terminal(vec![("zz", 100)])),
+            ],
+        );
+
+        let mut diff: Vec<_> = left
+            .diff(&ctx, &blobstore, right)
+            .try_collect()
+            .await
+            .unwrap();
+        diff.sort_by_key(|entry| match entry {
+            DiffEntry::Added(key, _) => key.clone(),
+            DiffEntry::Removed(key, _) => key.clone(),
+            DiffEntry::Changed(key, _, _) => key.clone(),
+        });
+
+        assert_eq!(diff.len(), 3);
+        assert!(matches!(
+            &diff[0],
+            DiffEntry::Changed(key, MyType(7), MyType(70)) if key == b"abacab"
+        ));
+        assert!(matches!(
+            &diff[1],
+            DiffEntry::Removed(key, MyType(2)) if key == b"omiux"
+        ));
+        assert!(matches!(
+            &diff[2],
+            DiffEntry::Added(key, MyType(100)) if key == b"zzz"
+        ));
+    }
+
+    async fn collect_sorted(
+        ctx: &CoreContext,
+        blobstore: &Memblob,
+        map: ShardedMapNode<MyType>,
+    ) -> Vec<(Vec<u8>, MyType)> {
+        let mut entries: Vec<_> = map
+            .into_entries(ctx, blobstore)
+            .try_collect()
+            .await
+            .unwrap();
+        entries.sort();
+        entries
+    }
+
+    #[fbinit::test]
+    async fn test_insert_remove(fb: FacebookInit) {
+        let ctx = CoreContext::test_mock(fb);
+        let blobstore = Memblob::default();
+
+        let map = ShardedMapNode::Terminal {
+            values: Default::default(),
+        };
+        let map = map
+            .insert(&ctx, &blobstore, b"abc", MyType(1))
+            .await
+            .unwrap();
+        let map = map
+            .insert(&ctx, &blobstore, b"abd", MyType(2))
+            .await
+            .unwrap();
+        let map = map
+            .insert(&ctx, &blobstore, b"xyz", MyType(3))
+            .await
+            .unwrap();
+
+        assert_eq!(
+            collect_sorted(&ctx, &blobstore, map.clone()).await,
+            vec![
+                (b"abc".to_vec(), MyType(1)),
+                (b"abd".to_vec(), MyType(2)),
+                (b"xyz".to_vec(), MyType(3)),
+            ]
+        );
+        assert_eq!(
+            map.lookup(&ctx, &blobstore, b"abc").await.unwrap(),
+            Some(MyType(1))
+        );
+
+        let map = map.remove(&ctx, &blobstore, b"abd").await.unwrap();
+        assert_eq!(map.lookup(&ctx, &blobstore, b"abd").await.unwrap(), None);
+        assert_eq!(
+            collect_sorted(&ctx, &blobstore, map).await,
+            vec![(b"abc".to_vec(), MyType(1)), (b"xyz".to_vec(), MyType(3))],
+        );
+    }
+
+    #[fbinit::test]
+    async fn test_extend_reshards(fb: FacebookInit) {
+        let ctx = CoreContext::test_mock(fb);
+        let blobstore = Memblob::default();
+
+        let map = ShardedMapNode::Terminal {
+            values: Default::default(),
+        };
+        let updates = (0..(TERMINAL_SPLIT_THRESHOLD * 2) as i32)
+            .map(|i| (format!("key{:06}", i).into_bytes(), Some(MyType(i))))
+            .collect::<Vec<_>>();
+        let mut expected = updates
+            .iter()
+            .map(|(key, value)| (key.clone(), value.unwrap()))
+            .collect::<Vec<_>>();
+        expected.sort();
+
+        let map = map.extend(&ctx, &blobstore, updates).await.unwrap();
+        assert!(matches!(map, ShardedMapNode::Intermediate { .. }));
+        assert_eq!(collect_sorted(&ctx, &blobstore, map.clone()).await, expected);
+
+        // Deleting almost everything should collapse the tree back down to
+        // a single Terminal node.
+        let deletes = expected[1..]
+            .iter()
+            .map(|(key, _)| (key.clone(), None))
+            .collect::<Vec<_>>();
+        let map = map.extend(&ctx, &blobstore, deletes).await.unwrap();
+        assert!(matches!(map, ShardedMapNode::Terminal { .. }));
+        assert_eq!(
+            collect_sorted(&ctx, &blobstore, map).await,
+            vec![expected[0].clone()],
+        );
+    }
+
+    #[cfg(feature = "json")]
+    #[test]
+    fn test_json_round_trip() {
+        let map = example_map();
+        let json = serde_json::to_string(&map).unwrap();
+        let roundtripped: ShardedMapNode<MyType> = serde_json::from_str(&json).unwrap();
+        assert_eq!(roundtripped, map);
+    }
 }